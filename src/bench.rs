@@ -0,0 +1,195 @@
+//! Benchmarks: lazy evaluation vs hand-written loops vs one memory pass per operation
+//!
+//! Run with `cargo run --release -- bench`. All the inputs are synthetic, and each measurement is
+//! the fastest of several runs.
+//!
+//! The unfused baseline evaluates one operation at a time, storing each intermediate result in a
+//! temporary, like a library without expression templates (e.g. BLAS calls) would. The
+//! hand-written loops always run in the calling thread.
+
+use std::cmp;
+
+use time;
+
+use ::{Col, Convert, Mat, Math, Policy};
+
+/// Number of elements of the vectors
+const N: u32 = 1 << 22;
+
+/// Number of pixels of the synthetic image, 1920x1080
+const NPIXELS: u32 = 1920 * 1080;
+
+/// Number of times each measurement is repeated
+const NRUNS: usize = 10;
+
+pub fn main() {
+    println!("{:<12} {:<10} {:<8} {:>12} {:>12} {:>12}",
+             "expression", "layout", "threads", "et (ns)", "loop (ns)", "unfused (ns)");
+
+    let policies = [("serial", Policy::serial()), ("parallel", Policy::new().threshold(1))];
+
+    for &(threads, policy) in &policies {
+        axpy(threads, policy);
+        axpy_strided(threads, policy);
+        broadcast(threads, policy);
+        norm(threads, policy);
+        grayscale(threads, policy);
+    }
+}
+
+/// Prints a row of the report
+fn report(expr: &str, layout: &str, threads: &str, et: u64, naive: u64, unfused: u64) {
+    println!("{:<12} {:<10} {:<8} {:>12} {:>12} {:>12}", expr, layout, threads, et, naive, unfused);
+}
+
+/// Returns the fastest of several runs of `f`, in nanoseconds
+fn fastest<F>(mut f: F) -> u64 where F: FnMut() {
+    (0..NRUNS).map(|_| {
+        let start = time::precise_time_ns();
+        f();
+        time::precise_time_ns() - start
+    }).fold(!0, cmp::min)
+}
+
+/// Returns a vector of `n` pseudo-random numbers in the `[0, 1)` range
+fn random(n: u32, seed: u32) -> Box<Col<f32>> {
+    let mut x = Col::zeros(n);
+    let mut state = seed;
+
+    for x in x.iter_mut() {
+        // linear congruential generator (Numerical Recipes)
+        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        *x = (state >> 8) as f32 / (1 << 24) as f32;
+    }
+
+    x
+}
+
+// `z = a * x + y`
+fn axpy(threads: &str, policy: Policy) {
+    let a = 2f32;
+    let x = random(N, 1);
+    let y = random(N, 2);
+    let mut z = Col::zeros(N);
+    let mut tmp = Col::zeros(N);
+
+    let et = fastest(|| z.assign_with(policy, ::Add(::Mul(a, &*x), &*y)));
+
+    let naive = fastest(|| {
+        for ((z, &x), &y) in z.iter_mut().zip(x.as_ref()).zip(y.as_ref()) {
+            *z = a * x + y
+        }
+    });
+
+    let unfused = fastest(|| {
+        tmp.assign_with(policy, ::Mul(a, &*x));
+        z.assign_with(policy, ::Add(&*tmp, &*y));
+    });
+
+    report("axpy", "contiguous", threads, et, naive, unfused);
+}
+
+// `z = a * x + y`, where `x` and `y` are the columns of a matrix
+fn axpy_strided(threads: &str, policy: Policy) {
+    let a = 2f32;
+    let xy = random(2 * N, 3);
+    let xy = Mat::reshape(xy.as_ref(), (N, 2));
+    let (x, y) = (&xy[.., 0], &xy[.., 1]);
+    let mut z = Col::zeros(N);
+    let mut tmp = Col::zeros(N);
+
+    let et = fastest(|| z.assign_with(policy, ::Add(::Mul(a, x), y)));
+
+    let naive = fastest(|| {
+        for (z, xy) in z.iter_mut().zip(xy.as_ref().chunks(2)) {
+            *z = a * xy[0] + xy[1]
+        }
+    });
+
+    let unfused = fastest(|| {
+        tmp.assign_with(policy, ::Mul(a, x));
+        z.assign_with(policy, ::Add(&*tmp, y));
+    });
+
+    report("axpy", "strided", threads, et, naive, unfused);
+}
+
+// `y = x * 2 + 1`
+fn broadcast(threads: &str, policy: Policy) {
+    let x = random(N, 4);
+    let mut y = Col::zeros(N);
+    let mut tmp = Col::zeros(N);
+
+    let et = fastest(|| y.assign_with(policy, ::Add(1f32, ::Mul(2f32, &*x))));
+
+    let naive = fastest(|| {
+        for (y, &x) in y.iter_mut().zip(x.as_ref()) {
+            *y = x * 2. + 1.
+        }
+    });
+
+    let unfused = fastest(|| {
+        tmp.assign_with(policy, ::Mul(2f32, &*x));
+        y.assign_with(policy, ::Add(1f32, &*tmp));
+    });
+
+    report("broadcast", "contiguous", threads, et, naive, unfused);
+}
+
+// `z = sqrt(x * x + y * y)`, the euclidean norm of each `(x, y)` pair
+fn norm(threads: &str, policy: Policy) {
+    let x = random(N, 5);
+    let y = random(N, 6);
+    let mut z = Col::zeros(N);
+    let mut xx = Col::zeros(N);
+    let mut yy = Col::zeros(N);
+    let mut sum = Col::zeros(N);
+
+    let et = fastest(|| z.assign_with(policy, ::Add(::Mul(&*x, &*x), ::Mul(&*y, &*y)).sqrt()));
+
+    let naive = fastest(|| {
+        for ((z, &x), &y) in z.iter_mut().zip(x.as_ref()).zip(y.as_ref()) {
+            *z = (x * x + y * y).sqrt()
+        }
+    });
+
+    let unfused = fastest(|| {
+        xx.assign_with(policy, ::Mul(&*x, &*x));
+        yy.assign_with(policy, ::Mul(&*y, &*y));
+        sum.assign_with(policy, ::Add(&*xx, &*yy));
+        z.assign_with(policy, (&*sum).sqrt());
+    });
+
+    report("norm", "contiguous", threads, et, naive, unfused);
+}
+
+// RGB -> grayscale, see `cli.rs`
+fn grayscale(threads: &str, policy: Policy) {
+    let rgb = Mat::from_fn((NPIXELS, 3), |(i, j)| (i * 7 + j * 13) as u8);
+    let (r, g, b) = (&rgb[.., 0], &rgb[.., 1], &rgb[.., 2]);
+    let mut gray = Col::<u8>::zeros(NPIXELS);
+    let mut tmp1 = Col::<f32>::zeros(NPIXELS);
+    let mut tmp2 = Col::<f32>::zeros(NPIXELS);
+
+    let et = fastest(|| gray.assign_with(policy, {
+        ::Add(::Mul(0.2126f32, r.cast::<f32>()),
+              ::Add(::Mul(0.7152f32, g.cast::<f32>()), ::Mul(0.0722f32, b.cast::<f32>())))
+    }.rounding_cast::<u8>()));
+
+    let naive = fastest(|| {
+        for (gray, rgb) in gray.iter_mut().zip(rgb.as_ref().chunks(3)) {
+            let y = 0.2126 * rgb[0] as f32 + 0.7152 * rgb[1] as f32 + 0.0722 * rgb[2] as f32;
+
+            *gray = y.round() as u8
+        }
+    });
+
+    let unfused = fastest(|| {
+        tmp1.assign_with(policy, ::Mul(0.2126f32, r.cast::<f32>()));
+        tmp2.assign_with(policy, ::Add(::Mul(0.7152f32, g.cast::<f32>()), &*tmp1));
+        tmp1.assign_with(policy, ::Add(::Mul(0.0722f32, b.cast::<f32>()), &*tmp2));
+        gray.assign_with(policy, (&*tmp1).rounding_cast::<u8>());
+    });
+
+    report("grayscale", "strided", threads, et, naive, unfused);
+}
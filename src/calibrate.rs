@@ -0,0 +1,215 @@
+//! Calibration of the parallelism threshold
+//!
+//! The default threshold (see `policy`) was picked on a single machine. `run` measures, on the
+//! current host, the overhead of dispatching jobs to the thread pool and the time required to
+//! evaluate one unit of cost (see the `cost` module), and derives the smallest cost for which
+//! splitting an assignment in two halves pays off.
+//!
+//! Calibration is opt-in and takes a few tens of milliseconds:
+//!
+//! ``` ignore
+//! let calibration = et::calibrate::run();
+//! calibration.apply();  // affects all the subsequent assignments
+//! calibration.save("et-calibration.txt").unwrap();
+//! ```
+//!
+//! If the `ET_CALIBRATION` environment variable points to a file written by `Calibration::save`,
+//! its threshold is used to initialize the global policy (`ET_THRESHOLD` takes precedence).
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::{cmp, usize};
+
+use cast::From;
+use time;
+
+use cost;
+use policy::{self, Policy};
+use pool::{self, Job};
+use traits::Flops;
+
+/// Number of elements of the buffers used to measure the throughput
+const NELEMS: u32 = 1 << 20;
+
+/// Number of times each measurement is repeated, the fastest run is kept
+const NRUNS: usize = 10;
+
+/// Number of fork-joins per run when measuring the overhead of the thread pool
+const NJOINS: u64 = 100;
+
+/// The result of calibrating the parallelism threshold on the current host
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Calibration {
+    /// Time required to dispatch jobs to all the workers and wait for them, in nanoseconds
+    pub overhead_ns: u64,
+    /// Time required to evaluate one unit of cost, in picoseconds
+    pub ps_per_cost: u64,
+    /// The parallelism threshold derived from the above measurements
+    pub threshold: usize,
+}
+
+impl Calibration {
+    /// Uses the threshold of this calibration in the global policy
+    pub fn apply(&self) {
+        policy::set(policy::get().threshold(self.threshold))
+    }
+
+    /// Loads a calibration previously stored with `save`
+    pub fn load<P>(path: P) -> io::Result<Calibration> where P: AsRef<Path> {
+        let mut contents = String::new();
+        try!(try!(File::open(path)).read_to_string(&mut contents));
+
+        let mut overhead_ns = None;
+        let mut ps_per_cost = None;
+        let mut threshold = None;
+
+        for line in contents.lines() {
+            let mut words = line.split('=').map(|word| word.trim());
+
+            match (words.next(), words.next().and_then(|value| value.parse::<u64>().ok())) {
+                (Some("overhead_ns"), Some(value)) => overhead_ns = Some(value),
+                (Some("ps_per_cost"), Some(value)) => ps_per_cost = Some(value),
+                (Some("threshold"), Some(value)) => threshold = Some(value),
+                _ => {},
+            }
+        }
+
+        match (overhead_ns, ps_per_cost, threshold) {
+            (Some(overhead_ns), Some(ps_per_cost), Some(threshold)) if threshold > 0 => {
+                Ok(Calibration {
+                    overhead_ns: overhead_ns,
+                    ps_per_cost: ps_per_cost,
+                    threshold: saturate(threshold),
+                })
+            },
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "malformed calibration file")),
+        }
+    }
+
+    /// Stores this calibration in the file at `path`
+    pub fn save<P>(&self, path: P) -> io::Result<()> where P: AsRef<Path> {
+        let mut file = try!(File::create(path));
+
+        write!(file, "overhead_ns = {}\nps_per_cost = {}\nthreshold = {}\n",
+               self.overhead_ns, self.ps_per_cost, self.threshold)
+    }
+}
+
+/// Measures the overhead of the thread pool and the throughput of the evaluator on this host
+pub fn run() -> Calibration {
+    let nthreads = policy::get().get_num_threads();
+
+    let overhead_ns = fastest(|| {
+        let start = time::precise_time_ns();
+
+        for _ in 0..NJOINS {
+            pool::get().execute((0..nthreads).map(|_| Box::new(|| {}) as Job).collect());
+        }
+
+        (time::precise_time_ns() - start) / NJOINS
+    });
+
+    let x = ::Col::<f32>::zeros(NELEMS);
+    let mut y = ::Col::<f32>::zeros(NELEMS);
+    let rhs = ::Add(::Mul(2f32, &*x), &*x);
+    let cost = estimate(&rhs);
+
+    let elapsed_ns = fastest(|| {
+        let start = time::precise_time_ns();
+
+        y.assign_with(Policy::serial(), &rhs);
+
+        time::precise_time_ns() - start
+    });
+
+    let ps_per_cost = cmp::max(1, elapsed_ns.saturating_mul(1_000) / cost);
+
+    // NB Splitting an assignment in two halves saves half of its evaluation time, which must
+    // exceed the overhead of the dispatch
+    let threshold = cmp::max(1, overhead_ns.saturating_mul(2_000) / ps_per_cost);
+
+    let calibration = Calibration {
+        overhead_ns: overhead_ns,
+        ps_per_cost: ps_per_cost,
+        threshold: saturate(threshold),
+    };
+
+    debug!("{:?}", calibration);
+
+    calibration
+}
+
+/// Returns the estimated cost of evaluating `rhs` into a buffer of `NELEMS` elements
+fn estimate<R>(rhs: &R) -> u64 where R: Flops {
+    cmp::max(1, cost::of::<f32, R>(rhs, usize::from(NELEMS)) as u64)
+}
+
+/// Runs the measurement `f` several times, returns the smallest result
+fn fastest<F>(mut f: F) -> u64 where F: FnMut() -> u64 {
+    (0..NRUNS).map(|_| f()).min().unwrap()
+}
+
+/// Converts `x` into an `usize`, saturating on overflow
+fn saturate(x: u64) -> usize {
+    if x > usize::MAX as u64 { usize::MAX } else { x as usize }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::File;
+    use std::io::{ErrorKind, Write};
+    use std::path::PathBuf;
+
+    use super::Calibration;
+
+    /// Returns a path in the temporary directory, unique to each test
+    fn path(test: &str) -> PathBuf {
+        env::temp_dir().join(format!("et-calibration-{}.txt", test))
+    }
+
+    /// Loads a calibration from a file with the given contents
+    fn load(test: &str, contents: &str) -> Option<Calibration> {
+        let path = path(test);
+        File::create(&path).unwrap().write_all(contents.as_bytes()).unwrap();
+
+        Calibration::load(&path).ok()
+    }
+
+    #[test]
+    fn round_trip() {
+        let calibration = Calibration { overhead_ns: 20_000, ps_per_cost: 250, threshold: 160_000 };
+        let path = path("round-trip");
+
+        calibration.save(&path).unwrap();
+
+        assert_eq!(Calibration::load(&path).unwrap(), calibration);
+    }
+
+    #[test]
+    fn lenient() {
+        let expected = Calibration { overhead_ns: 1, ps_per_cost: 2, threshold: 3 };
+
+        // any order, extra whitespace and unknown keys
+        assert_eq!(load("lenient", "threshold=3\n  ps_per_cost =  2\nfoo = 4\noverhead_ns = 1"),
+                   Some(expected));
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(load("missing", "overhead_ns = 1\nps_per_cost = 2\n"), None);
+        assert_eq!(load("negative", "overhead_ns = 1\nps_per_cost = 2\nthreshold = -3\n"), None);
+        assert_eq!(load("not-a-number", "overhead_ns = 1\nps_per_cost = two\nthreshold = 3\n"),
+                   None);
+        assert_eq!(load("zero", "overhead_ns = 1\nps_per_cost = 2\nthreshold = 0\n"), None);
+        assert_eq!(load("empty", ""), None);
+    }
+
+    #[test]
+    fn not_found() {
+        let err = Calibration::load(path("not-found")).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+}
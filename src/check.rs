@@ -0,0 +1,105 @@
+//! Bounds checks performed on every leaf access when the `checked` feature is enabled
+//!
+//! The evaluator reads the leaves of an expression with `unsafe_get` (and its linear and packet
+//! variants), which don't perform bounds checks. When this crate is compiled with
+//! `--features checked`, each of these reads is checked against the size of the leaf and against
+//! the buffer that the leaf views, and an out of range access panics with the kind of leaf, the
+//! offending coordinates and the expression being evaluated instead of reading arbitrary memory.
+//! Without the feature these checks compile down to nothing.
+//!
+//! NB The leaves don't know which expression they belong to, so the evaluators record the
+//! description of the root expression (see `Expr::describe`) in a thread local variable around
+//! each chunk they evaluate, in the calling thread and in the worker threads alike.
+
+use std::cell::RefCell;
+use std::isize;
+
+use cast::From;
+
+use traits::Expr;
+
+thread_local!(static EXPR: RefCell<Option<String>> = RefCell::new(None));
+
+/// Returns the description of `expr` if the checks are enabled, to be passed to `within`
+pub fn describe<E: ?Sized>(expr: &E) -> Option<String> where E: Expr {
+    if cfg!(feature = "checked") {
+        Some(expr.describe().to_string())
+    } else {
+        None
+    }
+}
+
+/// Evaluates `f` in the current thread, reporting `expr` as the expression being evaluated if one
+/// of the checks fails
+pub fn within<F, R>(expr: &Option<String>, f: F) -> R where F: FnOnce() -> R {
+    /// Restores the previous expression, even if `f` panics
+    struct Restore(Option<String>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let prev = self.0.take();
+            EXPR.with(|e| *e.borrow_mut() = prev);
+        }
+    }
+
+    if expr.is_none() {
+        return f()
+    }
+
+    let _restore = Restore(EXPR.with(|e| e.borrow_mut().take()));
+    EXPR.with(|e| *e.borrow_mut() = expr.clone());
+
+    f()
+}
+
+/// Returns the expression being evaluated by the current thread, as a suffix of a failure message
+fn context() -> String {
+    EXPR.with(|e| match *e.borrow() {
+        None => String::new(),
+        Some(ref expr) => format!(" while evaluating {}", expr),
+    })
+}
+
+/// Checks that `(i, j)` is in range of the leaf `node`, which has size `(nrows, ncols)`
+#[inline(always)]
+pub fn index(node: &'static str, (nrows, ncols): (u32, u32), (i, j): (u32, u32)) {
+    if cfg!(feature = "checked") && (i >= nrows || j >= ncols) {
+        panic!("{}: index ({}, {}) out of range of a {}x{} leaf{}",
+               node, i, j, nrows, ncols, context())
+    }
+}
+
+/// Checks that the `lanes` elements starting at the linear index `i` are in range of the leaf
+/// `node`, which has `nelems` elements
+#[inline(always)]
+pub fn linear(node: &'static str, nelems: usize, i: usize, lanes: usize) {
+    if cfg!(feature = "checked") && (i >= nelems || nelems - i < lanes) {
+        panic!("{}: linear index {}..{} out of range of a leaf with {} elements{}",
+               node, i, i + lanes, nelems, context())
+    }
+}
+
+/// Checks that the element `(i, j)` of the leaf `node`, which is stored `offset` elements after
+/// the first one, lies within the allocation the leaf views, which has `alloc` elements from the
+/// first element of the leaf on. Returns the offset
+///
+/// NB `alloc` is the length of the underlying buffer, not the span of the leaf: a bogus stride
+/// yields offsets that are within the span computed from that very stride
+#[inline(always)]
+pub fn offset(node: &'static str, (i, j): (u32, u32), offset: usize, alloc: usize) -> isize {
+    if cfg!(feature = "checked") && (offset >= alloc || offset > isize::MAX as usize) {
+        panic!("{}: element ({}, {}) is stored at offset {}, outside of the {} elements of the \
+                underlying allocation{}", node, i, j, offset, alloc, context())
+    }
+
+    offset as isize
+}
+
+/// Returns the number of elements spanned by a strided vector of length `len`
+pub fn span(len: u32, stride: u32) -> usize {
+    if len == 0 {
+        0
+    } else {
+        usize::from(len - 1) * usize::from(stride) + 1
+    }
+}
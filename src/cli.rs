@@ -0,0 +1,211 @@
+//! Command line tool: arithmetic on the channels of an image
+//!
+//! Usage: `et <input> <output> <operation> [arguments]`, see `USAGE` for the list of operations.
+//! Every operation is a single assignment evaluated by the library, whose execution time is
+//! reported.
+
+extern crate env_logger;
+extern crate image;
+extern crate time;
+
+use std::env;
+use std::io::{self, Write};
+use std::ops::Deref;
+use std::process;
+
+use self::image::{GrayImage, ImageBuffer, Pixel, RgbImage};
+
+use et::color::{self, Target, Weights};
+use et::{Compare, Convert, Math};
+
+const USAGE: &'static str = "\
+Usage: et <input> <output> <operation> [arguments]
+
+Operations:
+    gray [rec601|rec709|rec2020|<r>,<g>,<b>]  weighted sum of the channels (default: rec709)
+    mix <m00>,<m01>,...,<m22>                 out[c] = m[c][0] * r + m[c][1] * g + m[c][2] * b
+    brightness-contrast <brightness> <contrast>
+                                              contrast * (x - 128) + 128 + brightness
+    gamma <gamma>                             255 * (x / 255) ^ gamma
+    invert                                    255 - x
+    threshold <level>                         255 if x > level, 0 otherwise
+
+    et bench                                  runs the benchmarks";
+
+/// An operation on the channels of an RGB image
+enum Op {
+    /// Grayscale conversion, weighted sum of the R, G and B channels
+    Gray(Weights),
+    /// Channel mixing, the rows of the matrix are the weights of each output channel
+    Mix([[f32; 3]; 3]),
+    /// `contrast * (x - 128) + 128 + brightness`, on every channel
+    BrightnessContrast(f32, f32),
+    /// `255 * (x / 255) ^ gamma`, on every channel
+    Gamma(f32),
+    /// `255 - x`, on every channel
+    Invert,
+    /// `255` if `x > level`, `0` otherwise, on every channel
+    Threshold(u8),
+}
+
+impl Op {
+    /// Parses the operation `name` and its `args`, returns `None` if they are not valid
+    fn parse(name: &str, args: &[String]) -> Option<Op> {
+        match (name, args.len()) {
+            ("gray", 0) => Some(Op::Gray(color::REC709)),
+            ("gray", 1) => match &*args[0] {
+                "rec601" => Some(Op::Gray(color::REC601)),
+                "rec709" => Some(Op::Gray(color::REC709)),
+                "rec2020" => Some(Op::Gray(color::REC2020)),
+                weights => floats(weights, 3).map(|w| {
+                    Op::Gray(Weights { r: w[0], g: w[1], b: w[2] })
+                }),
+            },
+            ("mix", 1) => floats(&args[0], 9).map(|m| {
+                Op::Mix([[m[0], m[1], m[2]], [m[3], m[4], m[5]], [m[6], m[7], m[8]]])
+            }),
+            ("brightness-contrast", 2) => match (args[0].parse(), args[1].parse()) {
+                (Ok(brightness), Ok(contrast)) => {
+                    Some(Op::BrightnessContrast(brightness, contrast))
+                },
+                _ => None,
+            },
+            ("gamma", 1) => args[0].parse().ok().map(Op::Gamma),
+            ("invert", 0) => Some(Op::Invert),
+            ("threshold", 1) => args[0].parse().ok().map(Op::Threshold),
+            _ => None,
+        }
+    }
+}
+
+pub fn main() {
+    env_logger::init().unwrap();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.len() < 3 {
+        usage()
+    }
+
+    let (input, output) = (&args[0], &args[1]);
+    let op = Op::parse(&args[2], &args[3..]).unwrap_or_else(|| usage());
+
+    let dynamic = image::open(input).unwrap_or_else(|e| fail(&format!("{}: {}", input, e)));
+    let img = dynamic.to_rgb();
+
+    let (width, height) = img.dimensions();
+
+    println!("Applying `{}` to a {}x{} RGB image", args[2], width, height);
+
+    // Split in color channels, without copying the image
+    let rgb = et::image::as_mat(&img);
+    let channels = et::image::rgb(&img);
+    let (r, g, b) = channels;
+
+    let elapsed = match op {
+        Op::Gray(w) => {
+            let elapsed = timed(|| { dynamic.to_luma(); });
+
+            println!("using `image::DynamicImage::to_luma`: {} ns", elapsed);
+
+            // NB Only allocation required for this transformation
+            let mut gray = GrayImage::new(width, height);
+
+            let elapsed = timed(|| {
+                color::rgb_to_gray(w, channels, et::image::as_col_mut(&mut gray))
+            });
+
+            save(output, gray);
+
+            elapsed
+        },
+        Op::Mix(m) => {
+            let mix = |w: [f32; 3]| {
+                (r.cast::<f32>() * w[0] + g.cast::<f32>() * w[1] + b.cast::<f32>() * w[2])
+                    .rounding_cast::<u8>()
+            };
+
+            let mut out = RgbImage::new(width, height);
+
+            // NB The output channels are interleaved as they are evaluated
+            let elapsed = timed(|| {
+                et::image::as_mat_mut(&mut out).store((mix(m[0]), mix(m[1]), mix(m[2]))).unwrap()
+            });
+
+            save(output, out);
+
+            elapsed
+        },
+        op => {
+            let mut out = RgbImage::new(width, height);
+
+            let elapsed = {
+                // NB The result is written directly into the pixels of `out`
+                let dst = et::image::as_mat_mut(&mut out);
+
+                match op {
+                    Op::BrightnessContrast(brightness, contrast) => {
+                        let offset = 128. * (1. - contrast) + brightness;
+
+                        timed(|| {
+                            dst[..] = rgb.cast::<f32>().mul_add(contrast, offset)
+                                         .rounding_cast::<u8>()
+                        })
+                    },
+                    Op::Gamma(gamma) => timed(|| {
+                        dst[..] = ((rgb.cast::<f32>() * (1. / 255.)).powf(gamma) * 255.)
+                            .rounding_cast::<u8>()
+                    }),
+                    Op::Invert => timed(|| {
+                        dst[..] = rgb.cast::<f32>().mul_add(-1., 255.).rounding_cast::<u8>()
+                    }),
+                    Op::Threshold(level) => timed(|| {
+                        dst[..] = et::select(rgb.gt_elem(level), 255u8, 0u8)
+                    }),
+                    Op::Gray(_) | Op::Mix(_) => unreachable!(),
+                }
+            };
+
+            save(output, out);
+
+            elapsed
+        },
+    };
+
+    println!("using expression templates: {} ns", elapsed);
+}
+
+/// Parses a comma separated list of exactly `n` floats
+fn floats(list: &str, n: usize) -> Option<Vec<f32>> {
+    let floats = list.split(',').map(|x| x.trim().parse().ok()).collect::<Option<Vec<f32>>>();
+
+    floats.and_then(|floats| if floats.len() == n { Some(floats) } else { None })
+}
+
+/// Returns the execution time of `f`, in nanoseconds
+fn timed<F>(f: F) -> u64 where F: FnOnce() {
+    let start = time::precise_time_ns();
+    f();
+    time::precise_time_ns() - start
+}
+
+/// Stores `img` in the file at `path`
+fn save<P, C>(path: &str, img: ImageBuffer<P, C>) where
+    C: Deref<Target=[u8]>,
+    P: Pixel<Subpixel=u8> + 'static,
+{
+    img.save(path)
+        .unwrap_or_else(|e| fail(&format!("{}: {}", path, e)))
+}
+
+/// Prints `msg` to stderr and exits with an error
+fn fail(msg: &str) -> ! {
+    let _ = writeln!(io::stderr(), "error: {}", msg);
+    process::exit(1)
+}
+
+/// Prints the usage to stderr and exits with an error
+fn usage() -> ! {
+    let _ = writeln!(io::stderr(), "{}", USAGE);
+    process::exit(1)
+}
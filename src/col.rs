@@ -1,9 +1,13 @@
 use std::num::Zero;
 use std::ops::Deref;
-use std::{iter, mem, slice};
+use std::{fmt, iter, mem, slice, u32};
 
 use cast::From;
 
+use check;
+use error::{Error, Result};
+use traits::{Describe, Flops, Layout, Matrix, Region, Shape, UnsafeGet, UnsafeGetLinear};
+
 impl<T> ::Col<T> {
     pub fn zeros(n: u32) -> Box<::Col<T>> where T: Clone + Zero {
         unsafe {
@@ -19,10 +23,39 @@ impl<T> ::Col<T> {
         }
     }
 
+    /// Views a slice as a column vector
+    pub fn from_slice(slice: &[T]) -> &::Col<T> {
+        ::Col::try_from_slice(slice).unwrap()
+    }
+
+    /// Views a mutable slice as a column vector
+    pub fn from_slice_mut(slice: &mut [T]) -> &mut ::Col<T> {
+        ::Col::try_from_slice_mut(slice).unwrap()
+    }
+
     pub fn iter_mut(&mut self) -> slice::IterMut<T> {
         self.as_mut().iter_mut()
     }
 
+    /// Same as `from_slice`, but returns an error if the slice has more than `u32::MAX` elements
+    pub fn try_from_slice(slice: &[T]) -> Result<&::Col<T>> {
+        let len = try!(len(slice));
+
+        unsafe {
+            Ok(mem::transmute(::raw::Slice { data: slice.as_ptr() as *mut T, len: len }))
+        }
+    }
+
+    /// Same as `from_slice_mut`, but returns an error if the slice has more than `u32::MAX`
+    /// elements
+    pub fn try_from_slice_mut(slice: &mut [T]) -> Result<&mut ::Col<T>> {
+        let len = try!(len(slice));
+
+        unsafe {
+            Ok(mem::transmute(::raw::Slice { data: slice.as_mut_ptr(), len: len }))
+        }
+    }
+
     fn repr(&self) -> ::raw::Slice<T> {
         unsafe {
             mem::transmute(self)
@@ -30,6 +63,15 @@ impl<T> ::Col<T> {
     }
 }
 
+/// Returns the length of `slice` as an `u32`
+fn len<T>(slice: &[T]) -> Result<u32> {
+    if slice.len() > u32::MAX as usize {
+        Err(Error::TooLong { len: slice.len() })
+    } else {
+        Ok(slice.len() as u32)
+    }
+}
+
 impl<T> AsMut<[T]> for ::Col<T> {
     fn as_mut(&mut self) -> &mut [T] {
         unsafe {
@@ -61,6 +103,7 @@ impl<T> Deref for ::Col<T> {
                 data: data,
                 len: len,
                 stride: 1,
+                alloc: usize::from(len),
             })
         }
     }
@@ -80,6 +123,83 @@ impl<T> Drop for ::Col<T> {
     }
 }
 
+impl<T> Describe for ::Col<T> {
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Col[{}]", self.nrows())
+    }
+}
+
+impl<T> Flops for ::Col<T> {
+    fn bytes() -> usize {
+        mem::size_of::<T>()
+    }
+
+    fn flops(&self) -> usize {
+        0
+    }
+}
+
+impl<T> Layout for ::Col<T> {
+    fn aliases(&self, dst: &Region) -> bool {
+        let slice = self.as_ref();
+        let start = slice.as_ptr() as usize;
+        let end = start + slice.len() * mem::size_of::<T>();
+
+        dst.is_aliased_by(start, end, (self.row_stride(), self.col_stride()))
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        mem::size_of::<T>()
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        mem::size_of::<T>()
+    }
+}
+
+impl<T> Matrix for ::Col<T> {
+    #[inline(always)]
+    fn nrows(&self) -> u32 {
+        self.repr().len
+    }
+
+    #[inline(always)]
+    fn ncols(&self) -> u32 {
+        1
+    }
+}
+
 unsafe impl<T> Send for ::Col<T> where T: Send {}
 
+impl<T> Shape for ::Col<T> {
+    type Kind = ::kind::Col;
+}
+
 unsafe impl<T> Sync for ::Col<T> where T: Sync {}
+
+impl<T> UnsafeGet for ::Col<T> where T: Clone {
+    type Output = T;
+
+    #[inline(always)]
+    unsafe fn unsafe_get(&self, (i, j): (u32, u32)) -> T {
+        check::index("Col", self.size(), (i, j));
+
+        (*self.repr().data.offset(i as isize)).clone()
+    }
+}
+
+impl<T> UnsafeGetLinear for ::Col<T> where T: Clone {
+    #[inline(always)]
+    fn is_linear(&self, ncols: u32) -> bool {
+        ncols == 1
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> T {
+        check::linear("Col", self.nelems(), i, 1);
+
+        (*self.repr().data.offset(i as isize)).clone()
+    }
+}
@@ -0,0 +1,599 @@
+//! Color space conversions
+//!
+//! The converters read the three channels of the input through `strided::Col` views, so they work
+//! both on planar images (`(&*r, &*g, &*b)`) and on interleaved ones (see `channels` and
+//! `image::rgb`). They write the three channels of the output in a single (parallel) pass, either
+//! to three vectors (planar output) or to a matrix with one row per pixel (interleaved output),
+//! see `Target`. The non-linear conversions (HSV and HSL) convert each pixel only once.
+//!
+//! ``` ignore
+//! let rgb = et::color::channels(&*rgb);
+//!
+//! // planar output
+//! et::color::rgb_to_ycbcr(rgb, (&mut *y, &mut *cb, &mut *cr));
+//!
+//! // interleaved output
+//! et::color::rgb_to_hsv(rgb, &mut *hsv);
+//! ```
+//!
+//! All the channels are `u8`. YCbCr is the full range variant used by JPEG. The hue of HSV and HSL
+//! is scaled from `[0, 360)` degrees to `[0, 255]`.
+//!
+//! Each converter panics if the channels have different sizes, or if they don't match the size of
+//! the destination; its `try_` variant returns an error instead.
+
+use std::cell::Cell;
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+
+use convert::{Rounding, Saturate};
+use cost;
+use error::{self, Error, Result};
+use kind;
+use leaf::Leaf;
+use math::Function;
+use traits::{Expr, Matrix, UnsafeGet};
+
+use ::Convert;
+
+/// Views into the three channels of an image
+pub type Channels<'a, T> = (&'a ::strided::Col<T>, &'a ::strided::Col<T>, &'a ::strided::Col<T>);
+
+/// Weights of the red, green and blue channels used to compute the luma of a pixel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Weights {
+    /// Weight of the red channel
+    pub r: f32,
+    /// Weight of the green channel
+    pub g: f32,
+    /// Weight of the blue channel
+    pub b: f32,
+}
+
+/// Luma weights of the Rec. 601 standard (SDTV)
+pub const REC601: Weights = Weights { r: 0.299, g: 0.587, b: 0.114 };
+
+/// Luma weights of the Rec. 709 standard (HDTV)
+pub const REC709: Weights = Weights { r: 0.2126, g: 0.7152, b: 0.0722 };
+
+/// Luma weights of the Rec. 2020 standard (UHDTV)
+pub const REC2020: Weights = Weights { r: 0.2627, g: 0.6780, b: 0.0593 };
+
+/// RGB -> YCbCr, one row per output channel, the last column is the offset
+const RGB_TO_YCBCR: [[f32; 4]; 3] = [
+    [0.299, 0.587, 0.114, 0.],
+    [-0.168736, -0.331264, 0.5, 128.],
+    [0.5, -0.418688, -0.081312, 128.],
+];
+
+/// YCbCr -> RGB, one row per output channel, the last column is the offset
+const YCBCR_TO_RGB: [[f32; 4]; 3] = [
+    [1., 0., 1.402, -179.456],
+    [1., -0.344136, -0.714136, 135.458816],
+    [1., 1.772, 0., -226.816],
+];
+
+/// Cost of converting a pixel with the functions of this module that are not linear, including
+/// reading its three channels
+const NONLINEAR_COST: usize = 20 + 1;
+
+/// The destination of a conversion that outputs three channels
+pub trait Target {
+    /// Evaluates the three output channels in a single pass and stores them
+    ///
+    /// Returns an error if the sizes of the channels don't match the size of the destination
+    fn store<A, B, C>(self, channels: (A, B, C)) -> Result<()> where
+        A: Expr<Output=u8, Kind=kind::Col> + Sync,
+        B: Expr<Output=u8, Kind=kind::Col> + Sync,
+        C: Expr<Output=u8, Kind=kind::Col> + Sync;
+
+    /// Stores `f(i)`, the three output channels of the pixel `i`, for each of the `n` pixels of
+    /// the destination, in a single pass where `f` is called once per pixel
+    ///
+    /// NB This is an ordinary (fused) assignment: it follows the parallelism policy, and is
+    /// recorded by `stats` and checked like any other
+    ///
+    /// Returns an error if the destination doesn't have `n` pixels
+    fn store_pixels<F>(self, n: u32, f: F) -> Result<()> where F: Fn(u32) -> [u8; 3] + Sync;
+}
+
+/// Planar output, one vector per channel
+impl<'a, 'b, 'c> Target for (&'a mut ::Col<u8>, &'b mut ::Col<u8>, &'c mut ::Col<u8>) {
+    fn store<A, B, C>(self, channels: (A, B, C)) -> Result<()> where
+        A: Expr<Output=u8, Kind=kind::Col> + Sync,
+        B: Expr<Output=u8, Kind=kind::Col> + Sync,
+        C: Expr<Output=u8, Kind=kind::Col> + Sync,
+    {
+        ::try_assign(self, channels)
+    }
+
+    fn store_pixels<F>(self, n: u32, f: F) -> Result<()> where F: Fn(u32) -> [u8; 3] + Sync {
+        let pixels = Pixels::new(n, f);
+        let pixels = &pixels;
+
+        ::try_assign(self, (::leaf(Plane(pixels, 0)), ::leaf(Plane(pixels, 1)),
+                            ::leaf(Plane(pixels, 2))))
+    }
+}
+
+/// Interleaved output, a matrix with one row per pixel and one column per channel
+impl<'a> Target for &'a mut ::Mat<u8> {
+    fn store<A, B, C>(self, (a, b, c): (A, B, C)) -> Result<()> where
+        A: Expr<Output=u8, Kind=kind::Col> + Sync,
+        B: Expr<Output=u8, Kind=kind::Col> + Sync,
+        C: Expr<Output=u8, Kind=kind::Col> + Sync,
+    {
+        try!(error::check_size(a.size(), b.size()));
+        try!(error::check_size(a.size(), c.size()));
+
+        self.try_assign(::leaf(Interleave(a, b, c)))
+    }
+
+    fn store_pixels<F>(self, n: u32, f: F) -> Result<()> where F: Fn(u32) -> [u8; 3] + Sync {
+        self.try_assign(::leaf(Pixels::new(n, f)))
+    }
+}
+
+/// Returns views into the channels of an interleaved image, a matrix with one row per pixel and
+/// three columns
+pub fn channels<T>(mat: &::Mat<T>) -> Channels<T> {
+    try_channels(mat).unwrap()
+}
+
+/// Converts RGB into grayscale, using the luma `weights`
+pub fn rgb_to_gray(weights: Weights, rgb: Channels<u8>, dst: &mut ::Col<u8>) {
+    try_rgb_to_gray(weights, rgb, dst).unwrap()
+}
+
+/// Converts RGB into YCbCr
+pub fn rgb_to_ycbcr<D>(rgb: Channels<u8>, dst: D) where D: Target {
+    try_rgb_to_ycbcr(rgb, dst).unwrap()
+}
+
+/// Converts YCbCr into RGB
+pub fn ycbcr_to_rgb<D>(ycbcr: Channels<u8>, dst: D) where D: Target {
+    try_ycbcr_to_rgb(ycbcr, dst).unwrap()
+}
+
+/// Converts RGB into HSV
+pub fn rgb_to_hsv<D>(rgb: Channels<u8>, dst: D) where D: Target {
+    try_rgb_to_hsv(rgb, dst).unwrap()
+}
+
+/// Converts HSV into RGB
+pub fn hsv_to_rgb<D>(hsv: Channels<u8>, dst: D) where D: Target {
+    try_hsv_to_rgb(hsv, dst).unwrap()
+}
+
+/// Converts RGB into HSL
+pub fn rgb_to_hsl<D>(rgb: Channels<u8>, dst: D) where D: Target {
+    try_rgb_to_hsl(rgb, dst).unwrap()
+}
+
+/// Converts HSL into RGB
+pub fn hsl_to_rgb<D>(hsl: Channels<u8>, dst: D) where D: Target {
+    try_hsl_to_rgb(hsl, dst).unwrap()
+}
+
+/// Same as `channels`, but returns an error if the matrix doesn't have three columns
+pub fn try_channels<T>(mat: &::Mat<T>) -> Result<Channels<T>> {
+    if mat.ncols() != 3 {
+        return Err(Error::ShapeMismatch { lhs: (mat.nrows(), 3), rhs: mat.size() })
+    }
+
+    Ok((&mat[.., 0], &mat[.., 1], &mat[.., 2]))
+}
+
+/// Same as `rgb_to_gray`, but returns an error if the sizes don't match
+pub fn try_rgb_to_gray(weights: Weights, rgb: Channels<u8>, dst: &mut ::Col<u8>) -> Result<()> {
+    try!(check(rgb));
+
+    dst.try_assign(affine([weights.r, weights.g, weights.b, 0.], rgb))
+}
+
+/// Same as `rgb_to_ycbcr`, but returns an error if the sizes don't match
+pub fn try_rgb_to_ycbcr<D>(rgb: Channels<u8>, dst: D) -> Result<()> where D: Target {
+    try!(check(rgb));
+
+    let m = RGB_TO_YCBCR;
+    dst.store((affine(m[0], rgb), affine(m[1], rgb), affine(m[2], rgb)))
+}
+
+/// Same as `ycbcr_to_rgb`, but returns an error if the sizes don't match
+pub fn try_ycbcr_to_rgb<D>(ycbcr: Channels<u8>, dst: D) -> Result<()> where D: Target {
+    try!(check(ycbcr));
+
+    let m = YCBCR_TO_RGB;
+    dst.store((affine(m[0], ycbcr), affine(m[1], ycbcr), affine(m[2], ycbcr)))
+}
+
+/// Same as `rgb_to_hsv`, but returns an error if the sizes don't match
+pub fn try_rgb_to_hsv<D>(rgb: Channels<u8>, dst: D) -> Result<()> where D: Target {
+    per_pixel(hsv, rgb, dst)
+}
+
+/// Same as `hsv_to_rgb`, but returns an error if the sizes don't match
+pub fn try_hsv_to_rgb<D>(hsv: Channels<u8>, dst: D) -> Result<()> where D: Target {
+    per_pixel(hsv_inv, hsv, dst)
+}
+
+/// Same as `rgb_to_hsl`, but returns an error if the sizes don't match
+pub fn try_rgb_to_hsl<D>(rgb: Channels<u8>, dst: D) -> Result<()> where D: Target {
+    per_pixel(hsl, rgb, dst)
+}
+
+/// Same as `hsl_to_rgb`, but returns an error if the sizes don't match
+pub fn try_hsl_to_rgb<D>(hsl: Channels<u8>, dst: D) -> Result<()> where D: Target {
+    per_pixel(hsl_inv, hsl, dst)
+}
+
+/// The sRGB -> linear transfer function, whose input and output are in the `[0, 1]` range
+///
+/// ``` ignore
+/// // computed in `f32`, the result is only quantized when it's stored
+/// linear[..] = ((srgb.cast::<f32>() * (1. / 255.)).apply(et::color::SrgbToLinear) * 255.)
+///     .rounding_cast::<u8>();
+/// ```
+#[derive(Clone, Copy)]
+pub struct SrgbToLinear;
+
+/// The linear -> sRGB transfer function, whose input and output are in the `[0, 1]` range
+#[derive(Clone, Copy)]
+pub struct LinearToSrgb;
+
+// NB The cost is dominated by `powf`
+macro_rules! transfer {
+    ($($ty:ident),+) => {
+        $(
+            impl Function<$ty> for SrgbToLinear {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 42 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty {
+                    if x <= 0.04045 { x / 12.92 } else { ((x + 0.055) / 1.055).powf(2.4) }
+                }
+            }
+
+            impl Function<$ty> for LinearToSrgb {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 42 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty {
+                    if x <= 0.0031308 { x * 12.92 } else { 1.055 * x.powf(1. / 2.4) - 0.055 }
+                }
+            }
+        )+
+    }
+}
+
+transfer!(f32, f64);
+
+/// A channel converted into `f32`
+type Channel<'a> = ::Apply<::convert::Lossless<f32>, &'a ::strided::Col<u8>>;
+
+/// `k + a * x + b * y + c * z`, rounded and clamped to the range of `u8`
+type Affine<'a> = ::Apply<Rounding<u8>, ::Add<f32, ::Add<::Mul<f32, Channel<'a>>,
+                                                          ::Add<::Mul<f32, Channel<'a>>,
+                                                                ::Mul<f32, Channel<'a>>>>>>;
+
+/// Lazily computes `w[3] + w[0] * x + w[1] * y + w[2] * z`
+fn affine(w: [f32; 4], (x, y, z): Channels<u8>) -> Affine {
+    ::Add(w[3], ::Add(::Mul(w[0], x.cast::<f32>()),
+                      ::Add(::Mul(w[1], y.cast::<f32>()), ::Mul(w[2], z.cast::<f32>()))))
+        .rounding_cast::<u8>()
+}
+
+/// Checks that the channels have the same size
+fn check((x, y, z): Channels<u8>) -> Result<()> {
+    try!(error::check_size(x.size(), y.size()));
+    error::check_size(x.size(), z.size())
+}
+
+/// Converts each pixel with the function `f`, whose inputs and outputs are in the `[0, 1]` range
+fn per_pixel<D>(f: fn([f32; 3]) -> [f32; 3], channels: Channels<u8>, dst: D) -> Result<()> where
+    D: Target,
+{
+    try!(check(channels));
+
+    let (x, y, z) = channels;
+
+    dst.store_pixels(x.nrows(), |i| {
+        // NB `check` verified that the three channels have the same size, and `store_pixels` only
+        // asks for the pixels in `0..x.nrows()`
+        let pixel = unsafe {
+            [x.unsafe_get((i, 0)), y.unsafe_get((i, 0)), z.unsafe_get((i, 0))]
+        };
+
+        let pixel = f([pixel[0] as f32 / 255., pixel[1] as f32 / 255., pixel[2] as f32 / 255.]);
+
+        [to_u8(pixel[0]), to_u8(pixel[1]), to_u8(pixel[2])]
+    })
+}
+
+/// Rounds `x`, scaled from `[0, 1]` to `[0, 255]`, to the nearest `u8`
+fn to_u8(x: f32) -> u8 {
+    u8::saturate((x * 255.).round())
+}
+
+/// The pixels `f(0)`, `f(1)`, ... of a conversion, interleaved into a matrix with one row per pixel
+///
+/// NB The evaluator reads one element at a time. To call `f` once per pixel, the last pixel that
+/// the current thread converted is remembered, which works because both targets read the three
+/// channels of a pixel one after the other
+struct Pixels<F> {
+    f: F,
+    /// Tells apart the pixels of different conversions in `LAST`
+    id: usize,
+    n: u32,
+}
+
+/// The identifier of the conversion, the index and the channels of the last pixel converted by
+/// this thread
+thread_local!(static LAST: Cell<(usize, u32, [u8; 3])> = Cell::new((0, 0, [0; 3])));
+
+/// The identifier of the next conversion, zero is never used
+static NEXT_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+
+impl<F> Pixels<F> where F: Fn(u32) -> [u8; 3] {
+    fn new(n: u32, f: F) -> Pixels<F> {
+        Pixels { f: f, id: NEXT_ID.fetch_add(1, Ordering::Relaxed) + 1, n: n }
+    }
+
+    /// Returns `f(i)`, converting the pixel only if it's not the last one this thread converted
+    fn pixel(&self, i: u32) -> [u8; 3] {
+        LAST.with(|last| match last.get() {
+            (id, j, pixel) if id == self.id && j == i => pixel,
+            _ => {
+                let pixel = (self.f)(i);
+                last.set((self.id, i, pixel));
+                pixel
+            },
+        })
+    }
+}
+
+impl<F> Leaf for Pixels<F> where F: Fn(u32) -> [u8; 3] {
+    type Elem = u8;
+    type Kind = kind::Mat;
+
+    fn flops(&self) -> usize {
+        // NB per element, rounded up
+        (NONLINEAR_COST + 2) / 3
+    }
+
+    fn get(&self, (i, j): (u32, u32)) -> u8 {
+        self.pixel(i)[j as usize]
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.n, 3)
+    }
+}
+
+/// One of the channels of `Pixels`, to be stored in a vector
+struct Plane<'a, F: 'a>(&'a Pixels<F>, usize);
+
+impl<'a, F> Leaf for Plane<'a, F> where F: Fn(u32) -> [u8; 3] {
+    type Elem = u8;
+    type Kind = kind::Col;
+
+    fn flops(&self) -> usize {
+        self.0.flops()
+    }
+
+    fn get(&self, (i, _): (u32, u32)) -> u8 {
+        self.0.pixel(i)[self.1]
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.0.n, 1)
+    }
+}
+
+/// Interleaves three channels into a matrix with one row per pixel
+struct Interleave<A, B, C>(A, B, C);
+
+impl<A, B, C> Leaf for Interleave<A, B, C> where
+    A: Expr<Output=u8>,
+    B: Expr<Output=u8>,
+    C: Expr<Output=u8>,
+{
+    type Elem = u8;
+    type Kind = kind::Mat;
+
+    fn flops(&self) -> usize {
+        // NB Each element is read from one of the channels, `cost::of` includes the bytes read
+        // from their leaves
+        (cost::of::<u8, A>(&self.0, 1) + cost::of::<u8, B>(&self.1, 1) +
+         cost::of::<u8, C>(&self.2, 1)) / 3
+    }
+
+    fn get(&self, (i, j): (u32, u32)) -> u8 {
+        // NB `Target::store` checked that the three channels have the same size
+        unsafe {
+            match j {
+                0 => self.0.unsafe_get((i, 0)),
+                1 => self.1.unsafe_get((i, 0)),
+                _ => self.2.unsafe_get((i, 0)),
+            }
+        }
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.0.nrows(), 3)
+    }
+}
+
+/// RGB -> HSV
+fn hsv(rgb: [f32; 3]) -> [f32; 3] {
+    let (max, min) = extrema(rgb);
+    let s = if max == 0. { 0. } else { (max - min) / max };
+
+    [hue(rgb, max, min), s, max]
+}
+
+/// HSV -> RGB
+fn hsv_inv(hsv: [f32; 3]) -> [f32; 3] {
+    let (h, s, v) = (hsv[0], hsv[1], hsv[2]);
+    let c = v * s;
+
+    from_hue(h, c, v - c)
+}
+
+/// RGB -> HSL
+fn hsl(rgb: [f32; 3]) -> [f32; 3] {
+    let (max, min) = extrema(rgb);
+    let l = (max + min) / 2.;
+    let s = if max == min { 0. } else { (max - min) / (1. - (2. * l - 1.).abs()) };
+
+    [hue(rgb, max, min), s, l]
+}
+
+/// HSL -> RGB
+fn hsl_inv(hsl: [f32; 3]) -> [f32; 3] {
+    let (h, s, l) = (hsl[0], hsl[1], hsl[2]);
+    let c = (1. - (2. * l - 1.).abs()) * s;
+
+    from_hue(h, c, l - c / 2.)
+}
+
+/// Returns the largest and the smallest components of an RGB pixel
+fn extrema(rgb: [f32; 3]) -> (f32, f32) {
+    (rgb[0].max(rgb[1]).max(rgb[2]), rgb[0].min(rgb[1]).min(rgb[2]))
+}
+
+/// Returns the hue of an RGB pixel, in the `[0, 1)` range
+fn hue(rgb: [f32; 3], max: f32, min: f32) -> f32 {
+    let (r, g, b) = (rgb[0], rgb[1], rgb[2]);
+    let d = max - min;
+
+    // sector of the color hexagon, in the `[-1, 5)` range
+    let h = if d == 0. {
+        0.
+    } else if max == r {
+        (g - b) / d
+    } else if max == g {
+        (b - r) / d + 2.
+    } else {
+        (r - g) / d + 4.
+    };
+
+    if h < 0. { h / 6. + 1. } else { h / 6. }
+}
+
+/// Returns the RGB pixel with hue `h`, chroma `c` and the smallest component equal to `m`
+fn from_hue(h: f32, c: f32, m: f32) -> [f32; 3] {
+    let h = h * 6.;
+    let x = c * (1. - (h % 2. - 1.).abs());
+
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.),
+        1 => (x, c, 0.),
+        2 => (0., c, x),
+        3 => (0., x, c),
+        4 => (x, 0., c),
+        _ => (c, 0., x),
+    };
+
+    [r + m, g + m, b + m]
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Function;
+
+    use super::{LinearToSrgb, SrgbToLinear};
+
+    /// Samples of the RGB cube, every 15 levels of each channel, in the `[0, 1]` range
+    fn samples() -> Vec<[f32; 3]> {
+        let levels = (0..18).map(|i| i as f32 / 17.).collect::<Vec<_>>();
+        let mut samples = vec![];
+
+        for &r in &levels {
+            for &g in &levels {
+                for &b in &levels {
+                    samples.push([r, g, b])
+                }
+            }
+        }
+
+        samples
+    }
+
+    /// Checks that `f` inverts `g` on the RGB cube
+    fn round_trip(g: fn([f32; 3]) -> [f32; 3], f: fn([f32; 3]) -> [f32; 3]) {
+        for rgb in samples() {
+            let back = f(g(rgb));
+
+            assert!(rgb.iter().zip(back.iter()).all(|(x, y)| (x - y).abs() < 1e-5),
+                    "{:?} came back as {:?}", rgb, back);
+        }
+    }
+
+    /// Red, green, blue and white, planar
+    const RGB: [[u8; 4]; 3] = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+
+    #[test]
+    fn hsv() {
+        round_trip(super::hsv, super::hsv_inv);
+
+        let (r, g, b) = (::Col::from_slice(&RGB[0]), ::Col::from_slice(&RGB[1]),
+                         ::Col::from_slice(&RGB[2]));
+        let mut hsv = [0u8; 12];
+        let mut rgb = [0u8; 12];
+
+        super::rgb_to_hsv((&**r, &**g, &**b), ::Mat::reshape_mut(&mut hsv, (4, 3)));
+        super::hsv_to_rgb(super::channels(::Mat::reshape(&hsv, (4, 3))),
+                          ::Mat::reshape_mut(&mut rgb, (4, 3)));
+
+        // NB the hue is scaled from degrees to `[0, 255]`, green is at 120, blue at 240
+        assert_eq!(hsv, [0, 255, 255, 85, 255, 255, 170, 255, 255, 0, 0, 255]);
+        assert_eq!(rgb, [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn hsl() {
+        round_trip(super::hsl, super::hsl_inv);
+
+        let (r, g, b) = (::Col::from_slice(&RGB[0]), ::Col::from_slice(&RGB[1]),
+                         ::Col::from_slice(&RGB[2]));
+        let (mut h, mut s, mut l) = ([0u8; 4], [0u8; 4], [0u8; 4]);
+        let mut rgb = [0u8; 12];
+
+        {
+            let hsl = (::Col::from_slice_mut(&mut h), ::Col::from_slice_mut(&mut s),
+                       ::Col::from_slice_mut(&mut l));
+
+            // planar output
+            super::rgb_to_hsl((&**r, &**g, &**b), hsl);
+        }
+
+        {
+            let (h, s, l) = (::Col::from_slice(&h), ::Col::from_slice(&s), ::Col::from_slice(&l));
+
+            super::hsl_to_rgb((&**h, &**s, &**l), ::Mat::reshape_mut(&mut rgb, (4, 3)));
+        }
+
+        assert_eq!((h, s, l), ([0, 85, 170, 0], [255, 255, 255, 0], [128, 128, 128, 255]));
+        // NB the lightness of the primaries, 127.5, gets rounded up so they come back lighter
+        assert_eq!(rgb, [255, 1, 1, 1, 255, 1, 1, 1, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn srgb() {
+        assert_eq!(SrgbToLinear.call(0f32), 0.);
+        assert_eq!(LinearToSrgb.call(0f32), 0.);
+
+        for i in 0..256 {
+            let x = i as f32 / 255.;
+            let y = LinearToSrgb.call(SrgbToLinear.call(x));
+
+            assert!((x - y).abs() < 1e-5, "{} came back as {}", x, y);
+        }
+    }
+}
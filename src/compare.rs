@@ -0,0 +1,505 @@
+use std::{cmp, fmt};
+
+use cost::Arith;
+use error::{self, Result};
+use kind::Broadcast;
+use traits::{Describe, Flops, Layout, Matrix, Operand, Region, Shape, UnsafeGet, UnsafeGetLinear};
+
+use ::CmpOp;
+
+/// Lazy element-wise comparisons and boolean reductions
+///
+/// NB The comparisons are suffixed with `_elem` because this trait is also implemented for the
+/// scalars, where `gt`, `lt`, etc would be ambiguous with the methods of `PartialOrd`
+pub trait Compare: UnsafeGet + Sized {
+    /// Lazily computes `self == rhs`, element-wise
+    fn eq_elem<B>(self, rhs: B) -> ::Cmp<Self, B> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        self.try_eq_elem(rhs).unwrap()
+    }
+
+    /// Lazily computes `self >= rhs`, element-wise
+    fn ge_elem<B>(self, rhs: B) -> ::Cmp<Self, B> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        self.try_ge_elem(rhs).unwrap()
+    }
+
+    /// Lazily computes `self > rhs`, element-wise
+    fn gt_elem<B>(self, rhs: B) -> ::Cmp<Self, B> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        self.try_gt_elem(rhs).unwrap()
+    }
+
+    /// Lazily computes `self <= rhs`, element-wise
+    fn le_elem<B>(self, rhs: B) -> ::Cmp<Self, B> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        self.try_le_elem(rhs).unwrap()
+    }
+
+    /// Lazily computes `self < rhs`, element-wise
+    fn lt_elem<B>(self, rhs: B) -> ::Cmp<Self, B> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        self.try_lt_elem(rhs).unwrap()
+    }
+
+    /// Lazily computes `self != rhs`, element-wise
+    fn ne_elem<B>(self, rhs: B) -> ::Cmp<Self, B> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        self.try_ne_elem(rhs).unwrap()
+    }
+
+    /// Evaluates this mask and checks if all its elements are `true`
+    fn all(&self) -> bool where Self: Matrix + UnsafeGet<Output=bool> {
+        let (nrows, ncols) = self.size();
+
+        unsafe {
+            for i in 0..nrows {
+                for j in 0..ncols {
+                    if !self.unsafe_get((i, j)) {
+                        return false
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Evaluates this mask and checks if any of its elements is `true`
+    fn any(&self) -> bool where Self: Matrix + UnsafeGet<Output=bool> {
+        let (nrows, ncols) = self.size();
+
+        unsafe {
+            for i in 0..nrows {
+                for j in 0..ncols {
+                    if self.unsafe_get((i, j)) {
+                        return true
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Evaluates this mask and returns the number of elements that are `true`
+    fn count(&self) -> usize where Self: Matrix + UnsafeGet<Output=bool> {
+        let (nrows, ncols) = self.size();
+        let mut n = 0;
+
+        unsafe {
+            for i in 0..nrows {
+                for j in 0..ncols {
+                    if self.unsafe_get((i, j)) {
+                        n += 1;
+                    }
+                }
+            }
+        }
+
+        n
+    }
+
+    /// Same as `eq_elem`, but returns an error if the operands have different sizes
+    fn try_eq_elem<B>(self, rhs: B) -> Result<::Cmp<Self, B>> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        cmp(self, rhs, CmpOp::Eq)
+    }
+
+    /// Same as `ge_elem`, but returns an error if the operands have different sizes
+    fn try_ge_elem<B>(self, rhs: B) -> Result<::Cmp<Self, B>> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        cmp(self, rhs, CmpOp::Ge)
+    }
+
+    /// Same as `gt_elem`, but returns an error if the operands have different sizes
+    fn try_gt_elem<B>(self, rhs: B) -> Result<::Cmp<Self, B>> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        cmp(self, rhs, CmpOp::Gt)
+    }
+
+    /// Same as `le_elem`, but returns an error if the operands have different sizes
+    fn try_le_elem<B>(self, rhs: B) -> Result<::Cmp<Self, B>> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        cmp(self, rhs, CmpOp::Le)
+    }
+
+    /// Same as `lt_elem`, but returns an error if the operands have different sizes
+    fn try_lt_elem<B>(self, rhs: B) -> Result<::Cmp<Self, B>> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        cmp(self, rhs, CmpOp::Lt)
+    }
+
+    /// Same as `ne_elem`, but returns an error if the operands have different sizes
+    fn try_ne_elem<B>(self, rhs: B) -> Result<::Cmp<Self, B>> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: PartialOrd<B::Output>,
+    {
+        cmp(self, rhs, CmpOp::Ne)
+    }
+}
+
+impl<E> Compare for E where E: UnsafeGet {}
+
+/// Builds a comparison, checking the size of the right operand
+fn cmp<A, B>(lhs: A, rhs: B, op: CmpOp) -> Result<::Cmp<A, B>> where
+    A: Matrix + UnsafeGet,
+    B: Operand + UnsafeGet,
+    A::Output: PartialOrd<B::Output>,
+{
+    try!(error::check_operand(lhs.size(), rhs.operand_size()));
+
+    Ok(::Cmp(lhs, rhs, op))
+}
+
+/// Lazily selects, element-wise, `a` where `mask` is `true` and `b` elsewhere
+pub fn select<M, A, B>(mask: M, a: A, b: B) -> ::Select<M, A, B> where
+    M: Matrix + UnsafeGet<Output=bool>,
+    A: Operand + UnsafeGet,
+    B: Operand + UnsafeGet<Output=A::Output>,
+{
+    try_select(mask, a, b).unwrap()
+}
+
+/// Same as `select`, but returns an error if the operands have different sizes
+pub fn try_select<M, A, B>(mask: M, a: A, b: B) -> Result<::Select<M, A, B>> where
+    M: Matrix + UnsafeGet<Output=bool>,
+    A: Operand + UnsafeGet,
+    B: Operand + UnsafeGet<Output=A::Output>,
+{
+    let size = mask.size();
+    try!(error::check_operand(size, a.operand_size()));
+    try!(error::check_operand(size, b.operand_size()));
+
+    Ok(::Select(mask, a, b))
+}
+
+impl<A, B> Describe for ::Cmp<A, B> where
+    A: Describe + UnsafeGet,
+    B: Describe + UnsafeGet,
+    A::Output: PartialOrd<B::Output>,
+{
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "Cmp({:?}, ", self.2));
+        try!(self.0.fmt_tree(f));
+        try!(f.write_str(", "));
+        try!(self.1.fmt_tree(f));
+        f.write_str(")")
+    }
+}
+
+impl<A, B> Flops for ::Cmp<A, B> where
+    A: Flops + UnsafeGet,
+    B: Flops + UnsafeGet,
+    A::Output: Arith + PartialOrd<B::Output>,
+{
+    #[inline(always)]
+    fn bytes() -> usize {
+        A::bytes() + B::bytes()
+    }
+
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        self.0.flops() + self.1.flops() + <A::Output as Arith>::cmp()
+    }
+}
+
+impl<A, B> Layout for ::Cmp<A, B> where
+    A: Layout + UnsafeGet,
+    B: Layout + UnsafeGet,
+    A::Output: PartialOrd<B::Output>,
+{
+    #[inline(always)]
+    fn aliases(&self, dst: &Region) -> bool {
+        self.0.aliases(dst) || self.1.aliases(dst)
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        cmp::max(self.0.col_stride(), self.1.col_stride())
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        cmp::max(self.0.row_stride(), self.1.row_stride())
+    }
+}
+
+// NB The scalar (if any) is placed on the right side of a comparison, so the size is taken from
+// the left operand
+impl<A, B> Matrix for ::Cmp<A, B> where
+    A: Matrix + UnsafeGet,
+    B: UnsafeGet,
+    A::Output: PartialOrd<B::Output>,
+{
+    #[inline(always)]
+    fn nrows(&self) -> u32 {
+        A::nrows(&self.0)
+    }
+
+    #[inline(always)]
+    fn ncols(&self) -> u32 {
+        A::ncols(&self.0)
+    }
+
+    #[inline(always)]
+    fn nelems(&self) -> usize {
+        A::nelems(&self.0)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> (u32, u32) {
+        A::size(&self.0)
+    }
+}
+
+impl<A, B> Shape for ::Cmp<A, B> where
+    A: Shape + UnsafeGet,
+    B: Shape + UnsafeGet,
+    A::Output: PartialOrd<B::Output>,
+    A::Kind: Broadcast<B::Kind>,
+{
+    type Kind = <A::Kind as Broadcast<B::Kind>>::Output;
+}
+
+impl<A, B> UnsafeGet for ::Cmp<A, B> where
+    A: UnsafeGet,
+    B: UnsafeGet,
+    A::Output: PartialOrd<B::Output>,
+{
+    type Output = bool;
+
+    #[inline(always)]
+    unsafe fn unsafe_get(&self, i: (u32, u32)) -> bool {
+        let a = self.0.unsafe_get(i);
+        let b = self.1.unsafe_get(i);
+
+        match self.2 {
+            CmpOp::Eq => a == b,
+            CmpOp::Ge => a >= b,
+            CmpOp::Gt => a > b,
+            CmpOp::Le => a <= b,
+            CmpOp::Lt => a < b,
+            CmpOp::Ne => a != b,
+        }
+    }
+}
+
+impl<A, B> UnsafeGetLinear for ::Cmp<A, B> where
+    A: UnsafeGetLinear,
+    B: UnsafeGetLinear,
+    A::Output: PartialOrd<B::Output>,
+{
+    #[inline(always)]
+    fn is_linear(&self, ncols: u32) -> bool {
+        self.0.is_linear(ncols) && self.1.is_linear(ncols)
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> bool {
+        let a = self.0.unsafe_get_linear(i);
+        let b = self.1.unsafe_get_linear(i);
+
+        match self.2 {
+            CmpOp::Eq => a == b,
+            CmpOp::Ge => a >= b,
+            CmpOp::Gt => a > b,
+            CmpOp::Le => a <= b,
+            CmpOp::Lt => a < b,
+            CmpOp::Ne => a != b,
+        }
+    }
+}
+
+impl<M, A, B> Describe for ::Select<M, A, B> where
+    M: Describe + UnsafeGet<Output=bool>,
+    A: Describe + UnsafeGet,
+    B: Describe + UnsafeGet<Output=A::Output>,
+{
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(f.write_str("Select("));
+        try!(self.0.fmt_tree(f));
+        try!(f.write_str(", "));
+        try!(self.1.fmt_tree(f));
+        try!(f.write_str(", "));
+        try!(self.2.fmt_tree(f));
+        f.write_str(")")
+    }
+}
+
+// NB Only one of the branches is evaluated per element, the most expensive one is used as the
+// estimate. Picking the element costs as much as an addition
+impl<M, A, B> Flops for ::Select<M, A, B> where
+    M: Flops + UnsafeGet<Output=bool>,
+    A: Flops + UnsafeGet,
+    B: Flops + UnsafeGet<Output=A::Output>,
+    A::Output: Arith,
+{
+    #[inline(always)]
+    fn bytes() -> usize {
+        M::bytes() + cmp::max(A::bytes(), B::bytes())
+    }
+
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        self.0.flops() + cmp::max(self.1.flops(), self.2.flops()) + <A::Output as Arith>::add()
+    }
+}
+
+impl<M, A, B> Layout for ::Select<M, A, B> where
+    M: Layout + UnsafeGet<Output=bool>,
+    A: Layout + UnsafeGet,
+    B: Layout + UnsafeGet<Output=A::Output>,
+{
+    #[inline(always)]
+    fn aliases(&self, dst: &Region) -> bool {
+        self.0.aliases(dst) || self.1.aliases(dst) || self.2.aliases(dst)
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        cmp::max(self.0.col_stride(), cmp::max(self.1.col_stride(), self.2.col_stride()))
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        cmp::max(self.0.row_stride(), cmp::max(self.1.row_stride(), self.2.row_stride()))
+    }
+}
+
+impl<M, A, B> Matrix for ::Select<M, A, B> where
+    M: Matrix + UnsafeGet<Output=bool>,
+    A: UnsafeGet,
+    B: UnsafeGet<Output=A::Output>,
+{
+    #[inline(always)]
+    fn nrows(&self) -> u32 {
+        M::nrows(&self.0)
+    }
+
+    #[inline(always)]
+    fn ncols(&self) -> u32 {
+        M::ncols(&self.0)
+    }
+
+    #[inline(always)]
+    fn nelems(&self) -> usize {
+        M::nelems(&self.0)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> (u32, u32) {
+        M::size(&self.0)
+    }
+}
+
+impl<M, A, B> Shape for ::Select<M, A, B> where
+    M: Shape + UnsafeGet<Output=bool>,
+    A: Shape + UnsafeGet,
+    B: Shape + UnsafeGet<Output=A::Output>,
+    M::Kind: Broadcast<A::Kind>,
+    <M::Kind as Broadcast<A::Kind>>::Output: Broadcast<B::Kind>,
+{
+    type Kind = <<M::Kind as Broadcast<A::Kind>>::Output as Broadcast<B::Kind>>::Output;
+}
+
+impl<M, A, B> UnsafeGet for ::Select<M, A, B> where
+    M: UnsafeGet<Output=bool>,
+    A: UnsafeGet,
+    B: UnsafeGet<Output=A::Output>,
+{
+    type Output = A::Output;
+
+    #[inline(always)]
+    unsafe fn unsafe_get(&self, i: (u32, u32)) -> A::Output {
+        if self.0.unsafe_get(i) {
+            self.1.unsafe_get(i)
+        } else {
+            self.2.unsafe_get(i)
+        }
+    }
+}
+
+impl<M, A, B> UnsafeGetLinear for ::Select<M, A, B> where
+    M: UnsafeGetLinear<Output=bool>,
+    A: UnsafeGetLinear,
+    B: UnsafeGetLinear<Output=A::Output>,
+{
+    #[inline(always)]
+    fn is_linear(&self, ncols: u32) -> bool {
+        self.0.is_linear(ncols) && self.1.is_linear(ncols) && self.2.is_linear(ncols)
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> A::Output {
+        if self.0.unsafe_get_linear(i) {
+            self.1.unsafe_get_linear(i)
+        } else {
+            self.2.unsafe_get_linear(i)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use error::Error;
+
+    use super::{Compare, try_select};
+
+    #[test]
+    fn mismatch() {
+        let (x, y) = ([1f32, 2., 3.], [1f32, 2.]);
+        let (x, y) = (::Col::from_slice(&x), ::Col::from_slice(&y));
+
+        assert!(x.try_gt_elem(2f32).is_ok());
+        assert_eq!(x.try_gt_elem(y).err(), Some(Error::ShapeMismatch { lhs: (3, 1), rhs: (2, 1) }));
+    }
+
+    #[test]
+    fn select() {
+        let mask = [true, false, true];
+        let mask = ::Col::from_slice(&mask);
+        let (x, y) = ([1f32, 2., 3.], [1f32, 2.]);
+        let (x, y) = (::Col::from_slice(&x), ::Col::from_slice(&y));
+
+        assert!(try_select(mask, x, 0f32).is_ok());
+        assert_eq!(try_select(mask, x, y).err(),
+                   Some(Error::ShapeMismatch { lhs: (3, 1), rhs: (2, 1) }));
+        assert_eq!(try_select(mask, y, x).err(),
+                   Some(Error::ShapeMismatch { lhs: (3, 1), rhs: (2, 1) }));
+    }
+}
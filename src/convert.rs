@@ -0,0 +1,320 @@
+//! Lazy element type conversions
+//!
+//! Conversions are performed using the `cast` crate, so lossy conversions are never performed
+//! silently: either the conversion is lossless, or the overflow is reported (`checked_cast`),
+//! clamped (`saturating_cast`) or rounded and then clamped (`rounding_cast`).
+//!
+//! The elements of a `checked_cast` are `Result`s, evaluate it with `try_assign_checked` to get
+//! an error if any of them overflowed:
+//!
+//! ``` ignore
+//! // 16-bit samples into 8-bit ones
+//! if let Err(Error::Cast) = dst.try_assign_checked(src.checked_cast::<u8>()) {
+//!     println!("some samples don't fit in a byte")
+//! }
+//! ```
+
+use std::marker::PhantomData;
+use std::num::Zero;
+use std::result;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use cast;
+
+use error::{Error, Result};
+use math::{Function, Math};
+use traits::{Expr, UnsafeGet};
+
+/// Lossless conversion into `U`
+pub struct Lossless<U>(PhantomData<U>);
+
+/// Conversion into `U` that reports overflow
+pub struct Checked<U>(PhantomData<U>);
+
+/// Conversion into `U` that clamps the element to the range of `U`
+pub struct Saturating<U>(PhantomData<U>);
+
+/// Conversion into `U` that rounds the element to the nearest integer, and then clamps it to the
+/// range of `U`
+pub struct Rounding<U>(PhantomData<U>);
+
+/// Unwraps the result of a checked conversion, flagging the failed ones (see
+/// `try_assign_checked`)
+struct Report<'a>(&'a AtomicBool);
+
+/// Saturating conversion from `T`
+pub trait Saturate<T> {
+    /// Converts `x` into `Self`, clamping it to the range of `Self`. `NaN` is mapped to zero.
+    fn saturate(x: T) -> Self;
+}
+
+macro_rules! saturate {
+    ($src:ident => $($dst:ident),+) => {
+        $(
+            impl Saturate<$src> for $dst {
+                #[inline(always)]
+                fn saturate(x: $src) -> $dst {
+                    if x != x {
+                        0
+                    } else if x <= ::std::$dst::MIN as $src {
+                        ::std::$dst::MIN
+                    } else if x >= ::std::$dst::MAX as $src {
+                        ::std::$dst::MAX
+                    } else {
+                        x as $dst
+                    }
+                }
+            }
+         )+
+    }
+}
+
+// NB Negative integers fit in an `i64` and the non negative ones in an `u64`, so these comparisons
+// are exact for every pair of integer types
+macro_rules! saturate_int {
+    ($($src:ident),+) => {
+        $(
+            saturate_int!($src => i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+         )+
+    };
+    ($src:ident => $($dst:ident),+) => {
+        $(
+            impl Saturate<$src> for $dst {
+                #[allow(unused_comparisons)]
+                #[inline(always)]
+                fn saturate(x: $src) -> $dst {
+                    if x < 0 {
+                        if x as i64 <= ::std::$dst::MIN as i64 {
+                            ::std::$dst::MIN
+                        } else {
+                            x as $dst
+                        }
+                    } else if x as u64 >= ::std::$dst::MAX as u64 {
+                        ::std::$dst::MAX
+                    } else {
+                        x as $dst
+                    }
+                }
+            }
+         )+
+    };
+}
+
+saturate!(f32 => i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+saturate!(f64 => i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+saturate_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl<T, U> Function<T> for Lossless<U> where U: cast::From<T, Output=U> {
+    type Output = U;
+
+    #[inline(always)]
+    fn cost(&self) -> usize { 1 }
+
+    #[inline(always)]
+    fn call(&self, x: T) -> U {
+        <U as cast::From<T>>::from(x)
+    }
+}
+
+impl<T, U> Function<T> for Checked<U> where U: cast::From<T> {
+    type Output = U::Output;
+
+    #[inline(always)]
+    fn cost(&self) -> usize { 2 }
+
+    #[inline(always)]
+    fn call(&self, x: T) -> U::Output {
+        <U as cast::From<T>>::from(x)
+    }
+}
+
+impl<'a, U, E> Function<result::Result<U, E>> for Report<'a> where U: Zero {
+    type Output = U;
+
+    #[inline(always)]
+    fn cost(&self) -> usize { 1 }
+
+    #[inline(always)]
+    fn call(&self, x: result::Result<U, E>) -> U {
+        match x {
+            Ok(x) => x,
+            Err(_) => {
+                self.0.store(true, Ordering::Relaxed);
+                U::zero()
+            },
+        }
+    }
+}
+
+impl<T, U> Function<T> for Saturating<U> where U: Saturate<T> {
+    type Output = U;
+
+    #[inline(always)]
+    fn cost(&self) -> usize { 3 }
+
+    #[inline(always)]
+    fn call(&self, x: T) -> U {
+        U::saturate(x)
+    }
+}
+
+impl<U> Function<f32> for Rounding<U> where U: Saturate<f32> {
+    type Output = U;
+
+    #[inline(always)]
+    fn cost(&self) -> usize { 4 }
+
+    #[inline(always)]
+    fn call(&self, x: f32) -> U {
+        U::saturate(x.round())
+    }
+}
+
+impl<U> Function<f64> for Rounding<U> where U: Saturate<f64> {
+    type Output = U;
+
+    #[inline(always)]
+    fn cost(&self) -> usize { 4 }
+
+    #[inline(always)]
+    fn call(&self, x: f64) -> U {
+        U::saturate(x.round())
+    }
+}
+
+/// Lazy type conversions, available on every expression
+pub trait Convert: UnsafeGet + Sized {
+    /// Lazily converts each element into `U`, the conversion must be lossless
+    fn cast<U>(self) -> ::Apply<Lossless<U>, Self> where Lossless<U>: Function<Self::Output> {
+        ::Apply(Lossless(PhantomData), self)
+    }
+
+    /// Lazily converts each element into `U`, reporting overflow
+    ///
+    /// The elements are `Result`s, use `try_assign_checked` to evaluate this expression
+    fn checked_cast<U>(self) -> ::Apply<Checked<U>, Self> where
+        Checked<U>: Function<Self::Output>,
+    {
+        ::Apply(Checked(PhantomData), self)
+    }
+
+    /// Lazily rounds each element to the nearest integer and converts it into `U`, clamping it to
+    /// the range of `U`
+    fn rounding_cast<U>(self) -> ::Apply<Rounding<U>, Self> where
+        Rounding<U>: Function<Self::Output>,
+    {
+        ::Apply(Rounding(PhantomData), self)
+    }
+
+    /// Lazily converts each element into `U`, clamping it to the range of `U`
+    fn saturating_cast<U>(self) -> ::Apply<Saturating<U>, Self> where
+        Saturating<U>: Function<Self::Output>,
+    {
+        ::Apply(Saturating(PhantomData), self)
+    }
+}
+
+impl<E> Convert for E where E: UnsafeGet {}
+
+impl<T> ::Mat<T> {
+    /// Evaluates `rhs`, a `checked_cast`, and stores the converted elements in this matrix
+    ///
+    /// Returns `Error::Cast` if any of the elements overflowed, in which case the contents of this
+    /// matrix are unspecified, or an error if `rhs` doesn't have the same size as this matrix
+    pub fn try_assign_checked<R, E>(&mut self, rhs: R) -> Result<()> where
+        R: Expr<Output=result::Result<T, E>, Kind=::kind::Mat> + Sync,
+        T: Send + Zero,
+    {
+        let failed = AtomicBool::new(false);
+
+        try!(self.try_assign(rhs.apply(Report(&failed))));
+
+        if failed.load(Ordering::Relaxed) {
+            Err(Error::Cast)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<T> ::Col<T> {
+    /// Evaluates `rhs`, a `checked_cast`, and stores the converted elements in this vector
+    ///
+    /// Returns `Error::Cast` if any of the elements overflowed, in which case the contents of this
+    /// vector are unspecified, or an error if `rhs` doesn't have the same size as this vector
+    pub fn try_assign_checked<R, E>(&mut self, rhs: R) -> Result<()> where
+        R: Expr<Output=result::Result<T, E>, Kind=::kind::Col> + Sync,
+        T: Send + Zero,
+    {
+        let failed = AtomicBool::new(false);
+
+        try!(self.try_assign(rhs.apply(Report(&failed))));
+
+        if failed.load(Ordering::Relaxed) {
+            Err(Error::Cast)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f32, i64, i8, u64};
+
+    use error::Error;
+
+    use super::{Convert, Saturate};
+
+    #[test]
+    fn float_to_int() {
+        assert_eq!(u8::saturate(f32::NAN), 0);
+        assert_eq!(u8::saturate(-1f32), 0);
+        assert_eq!(u8::saturate(255.9f32), 255);
+        assert_eq!(u8::saturate(1e10f32), 255);
+        assert_eq!(u8::saturate(f32::INFINITY), 255);
+        assert_eq!(i8::saturate(f32::NEG_INFINITY), i8::MIN);
+        assert_eq!(i8::saturate(-128.5f64), i8::MIN);
+        assert_eq!(i8::saturate(-1.5f64), -1);
+    }
+
+    #[test]
+    fn int_to_int() {
+        assert_eq!(u8::saturate(300u16), 255);
+        assert_eq!(u8::saturate(200u16), 200);
+        assert_eq!(u8::saturate(-5i32), 0);
+        assert_eq!(i8::saturate(-200i32), i8::MIN);
+        assert_eq!(i8::saturate(200u8), i8::MAX);
+        assert_eq!(i64::saturate(u64::MAX), i64::MAX);
+        assert_eq!(u64::saturate(-1i64), 0);
+        assert_eq!(u64::saturate(i64::MAX), i64::MAX as u64);
+        assert_eq!(i64::saturate(i64::MIN), i64::MIN);
+    }
+
+    #[test]
+    fn checked() {
+        let src = ::Col::from_slice(&[1u16, 300, 2]);
+        let mut dst = [0u8; 3];
+
+        {
+            let dst = ::Col::from_slice_mut(&mut dst);
+
+            assert_eq!(dst.try_assign_checked(src.checked_cast::<u8>()), Err(Error::Cast));
+        }
+
+        let src = ::Col::from_slice(&[1u16, 255, 2]);
+        ::Col::from_slice_mut(&mut dst).try_assign_checked(src.checked_cast::<u8>()).unwrap();
+
+        assert_eq!(dst, [1, 255, 2]);
+    }
+
+    #[test]
+    fn rounding() {
+        let src = ::Col::from_slice(&[-0.5f32, 0.4, 2.5, 254.6, 256.]);
+        let mut dst = [0u8; 5];
+
+        ::Col::from_slice_mut(&mut dst)[..] = src.rounding_cast::<u8>();
+
+        assert_eq!(dst, [0, 0, 3, 255, 255]);
+    }
+}
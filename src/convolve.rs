@@ -0,0 +1,409 @@
+use std::marker::PhantomData;
+use std::num::Zero;
+use std::{fmt, mem};
+
+use cast::From;
+
+use cost::Arith;
+use kernel::Kernel;
+use traits::{Describe, Expr, Flops, Layout, Matrix, Region, Shape, UnsafeGet, UnsafeGetLinear};
+
+use ::Border;
+
+/// Lazy operations that read the neighbors of each element, available on every expression
+pub trait Stencil: UnsafeGet + Sized {
+    /// Lazily convolves this expression with `kernel`, which is centered on its coefficient
+    /// `(rows / 2, cols / 2)`. The neighbors that fall outside of this expression are read
+    /// according to `border`.
+    ///
+    /// NB Like in most image processing libraries, the kernel is not flipped (i.e. this is a
+    /// cross-correlation)
+    fn convolve<K>(self, kernel: K, border: Border) -> ::Convolve<Self, K> where
+        K: Kernel<Elem=Self::Output>,
+    {
+        ::Convolve(self, kernel, border)
+    }
+
+    /// Convolves this expression with the separable kernel whose rows are `row` and whose
+    /// columns are `col`, both 1D kernels. `row` is applied along the rows into a temporary
+    /// matrix, then `col` along its columns: `M + N` taps per element instead of the `M * N` of
+    /// the equivalent square kernel. The neighbors are read according to `border` in both passes.
+    ///
+    /// NB The row pass is evaluated right away (following the global policy), only the column
+    /// pass is lazy
+    ///
+    /// ``` ignore
+    /// // same as `img.convolve(GAUSSIAN3, Border::Reflect)`
+    /// let g = [0.25, 0.5, 0.25];
+    /// blurred[..] = img.convolve_separable(g, g, Border::Reflect);
+    /// ```
+    fn convolve_separable<R, C>(self, row: R, col: C, border: Border)
+        -> ::Separable<Self::Output, R, C> where
+        C: Kernel<Elem=Self::Output>,
+        R: Kernel<Elem=Self::Output> + Sync,
+        Self: Expr<Kind=::kind::Mat> + Sync,
+        Self::Output: Arith + Clone + Send + Zero,
+    {
+        let mut tmp = ::Mat::zeros(self.size());
+        tmp[..] = self.convolve(row, border);
+
+        ::Separable(tmp, col, border, PhantomData)
+    }
+
+    /// Lazily shifts this expression: the element `(i, j)` of the result is the element
+    /// `(i + dr, j + dc)` of this expression. The elements that fall outside of this expression
+    /// are read by clamping their coordinates, so e.g. forward differences are zero at the border.
+    ///
+    /// ``` ignore
+    /// // img: &Mat<f32>
+    /// dx[..] = img.shift(0, 1) - img;
+    /// ```
+    fn shift(self, dr: i32, dc: i32) -> ::Shift<Self> {
+        self.shift_with(dr, dc, Border::Clamp)
+    }
+
+    /// Same as `shift`, but the elements that fall outside of this expression are read according
+    /// to `border`
+    fn shift_with(self, dr: i32, dc: i32, border: Border) -> ::Shift<Self> {
+        ::Shift(self, (dr, dc), border)
+    }
+}
+
+impl<E> Stencil for E where E: UnsafeGet {}
+
+/// Returns the coordinate of the neighbor `i + d` along a dimension of length `n`, or `None` if
+/// it's out of range and `border` is `Border::Zero`
+#[inline(always)]
+pub fn neighbor(border: Border, i: u32, d: i64, n: u32) -> Option<u32> {
+    let k = i64::from(i) + d;
+    let n = i64::from(n);
+
+    if k >= 0 && k < n {
+        return Some(k as u32)
+    }
+
+    let k = match border {
+        Border::Zero => return None,
+        Border::Clamp => if k < 0 { 0 } else { n - 1 },
+        Border::Reflect => {
+            if n == 1 {
+                0
+            } else {
+                let period = 2 * (n - 1);
+                let k = (k % period + period) % period;
+
+                if k < n { k } else { period - k }
+            }
+        },
+        Border::Wrap => (k % n + n) % n,
+    };
+
+    Some(k as u32)
+}
+
+impl<M, K> Describe for ::Convolve<M, K> where
+    K: Kernel<Elem=M::Output>,
+    M: Describe + UnsafeGet,
+{
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (nrows, ncols) = K::size();
+
+        try!(write!(f, "Convolve({}x{}, {:?}, ", nrows, ncols, self.2));
+        try!(self.0.fmt_tree(f));
+        f.write_str(")")
+    }
+}
+
+// NB The operand is evaluated once per tap of the kernel. Its leaves are only counted once as
+// memory traffic, because the neighbors of an element are (mostly) read from the cache
+impl<M, K> Flops for ::Convolve<M, K> where
+    K: Kernel<Elem=M::Output>,
+    M: Flops + UnsafeGet,
+    M::Output: Arith,
+{
+    #[inline(always)]
+    fn bytes() -> usize {
+        M::bytes()
+    }
+
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        let op = <M::Output as Arith>::mul() + <M::Output as Arith>::add();
+
+        // NB plus one for locating the neighbor
+        K::taps() * (self.0.flops() + op + 1)
+    }
+}
+
+// NB Reading any element of the destination is a hazard: the neighbors of the element being
+// written may have been already overwritten
+impl<M, K> Layout for ::Convolve<M, K> where
+    K: Kernel<Elem=M::Output>,
+    M: Layout + UnsafeGet,
+{
+    #[inline(always)]
+    fn aliases(&self, dst: &Region) -> bool {
+        self.0.aliases(&Region { strides: None, ..*dst })
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        self.0.col_stride()
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        self.0.row_stride()
+    }
+}
+
+impl<M, K> Matrix for ::Convolve<M, K> where
+    K: Kernel<Elem=M::Output>,
+    M: Matrix + UnsafeGet,
+{
+    #[inline(always)]
+    fn nrows(&self) -> u32 {
+        M::nrows(&self.0)
+    }
+
+    #[inline(always)]
+    fn ncols(&self) -> u32 {
+        M::ncols(&self.0)
+    }
+
+    #[inline(always)]
+    fn nelems(&self) -> usize {
+        M::nelems(&self.0)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> (u32, u32) {
+        M::size(&self.0)
+    }
+}
+
+impl<M, K> Shape for ::Convolve<M, K> where
+    K: Kernel<Elem=M::Output>,
+    M: Shape + UnsafeGet,
+{
+    type Kind = M::Kind;
+}
+
+impl<M, K> UnsafeGet for ::Convolve<M, K> where
+    K: Kernel<Elem=M::Output>,
+    M: Matrix + UnsafeGet,
+    M::Output: Zero,
+{
+    type Output = M::Output;
+
+    #[inline(always)]
+    unsafe fn unsafe_get(&self, (i, j): (u32, u32)) -> M::Output {
+        let (nrows, ncols) = self.0.size();
+        let (krows, kcols) = K::size();
+        let (ci, cj) = (i64::from(krows / 2), i64::from(kcols / 2));
+        let border = self.2;
+
+        self.1.apply(|r, c| {
+            let i = neighbor(border, i, i64::from(r) - ci, nrows);
+            let j = neighbor(border, j, i64::from(c) - cj, ncols);
+
+            match (i, j) {
+                (Some(i), Some(j)) => self.0.unsafe_get((i, j)),
+                _ => <M::Output as Zero>::zero(),
+            }
+        })
+    }
+}
+
+// NB Each element depends on its neighbors, so this falls back to 2D indexing
+impl<M, K> UnsafeGetLinear for ::Convolve<M, K> where
+    K: Kernel<Elem=M::Output>,
+    M: Matrix + UnsafeGet,
+    M::Output: Zero,
+{
+    #[inline(always)]
+    fn is_linear(&self, _: u32) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> M::Output {
+        let ncols = usize::from(self.ncols());
+        let (r, c) = (i / ncols, i % ncols);
+
+        self.unsafe_get((r as u32, c as u32))
+    }
+}
+
+impl<T, R, C> Describe for ::Separable<T, R, C> where
+    C: Kernel<Elem=T>,
+    R: Kernel<Elem=T>,
+{
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "Separable({}, {}, {:?}, ", R::size().1, C::size().1, self.2));
+        try!(self.0.fmt_tree(f));
+        f.write_str(")")
+    }
+}
+
+// NB The row pass has already been evaluated, but both passes are counted so that the cost is
+// the one of the whole convolution, like for `Convolve`
+impl<T, R, C> Flops for ::Separable<T, R, C> where
+    C: Kernel<Elem=T>,
+    R: Kernel<Elem=T>,
+    T: Arith,
+{
+    #[inline(always)]
+    fn bytes() -> usize {
+        mem::size_of::<T>()
+    }
+
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        let op = T::mul() + T::add();
+
+        // NB plus one for locating the neighbor
+        (R::taps() + C::taps()) * (op + 1)
+    }
+}
+
+// NB The temporary is owned by this expression, so it can't alias the destination
+impl<T, R, C> Layout for ::Separable<T, R, C> where C: Kernel<Elem=T> {
+    fn aliases(&self, _: &Region) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        self.0.col_stride()
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        self.0.row_stride()
+    }
+}
+
+impl<T, R, C> Matrix for ::Separable<T, R, C> where C: Kernel<Elem=T> {
+    #[inline(always)]
+    fn size(&self) -> (u32, u32) {
+        self.0.size()
+    }
+}
+
+impl<T, R, C> Shape for ::Separable<T, R, C> where C: Kernel<Elem=T> {
+    type Kind = ::kind::Mat;
+}
+
+impl<T, R, C> UnsafeGet for ::Separable<T, R, C> where
+    C: Kernel<Elem=T>,
+    T: Clone + Zero,
+{
+    type Output = T;
+
+    // NB `col` is a 1D kernel, its coefficient `(0, r)` lines up with the row `i + r - N / 2`
+    #[inline(always)]
+    unsafe fn unsafe_get(&self, (i, j): (u32, u32)) -> T {
+        let nrows = self.0.nrows();
+        let ci = i64::from(C::size().1 / 2);
+        let border = self.2;
+
+        self.1.apply(|_, r| match neighbor(border, i, i64::from(r) - ci, nrows) {
+            Some(i) => self.0.unsafe_get((i, j)),
+            None => T::zero(),
+        })
+    }
+}
+
+// NB Each element depends on its neighbors, so this falls back to 2D indexing
+impl<T, R, C> UnsafeGetLinear for ::Separable<T, R, C> where
+    C: Kernel<Elem=T>,
+    T: Clone + Zero,
+{
+    #[inline(always)]
+    fn is_linear(&self, _: u32) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> T {
+        let ncols = usize::from(self.ncols());
+        let (r, c) = (i / ncols, i % ncols);
+
+        self.unsafe_get((r as u32, c as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kernel::GAUSSIAN3;
+
+    use ::Border;
+
+    use super::{Stencil, neighbor};
+
+    const BORDERS: [Border; 4] = [Border::Clamp, Border::Reflect, Border::Wrap, Border::Zero];
+
+    /// Returns the neighbors `-3..8` of the element `0` of a dimension of length 4
+    fn neighbors(border: Border) -> Vec<Option<u32>> {
+        (-3..8).map(|d| neighbor(border, 0, d, 4)).collect()
+    }
+
+    #[test]
+    fn in_range() {
+        for &border in &BORDERS {
+            assert_eq!(neighbor(border, 1, 2, 4), Some(3));
+            assert_eq!(neighbor(border, 3, -3, 4), Some(0));
+        }
+    }
+
+    #[test]
+    fn clamp() {
+        // aaa|abcd|dddd
+        assert_eq!(neighbors(Border::Clamp), [0, 0, 0, 0, 1, 2, 3, 3, 3, 3, 3].iter()
+                       .map(|&k| Some(k)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reflect() {
+        // dcb|abcd|cbab
+        assert_eq!(neighbors(Border::Reflect), [3, 2, 1, 0, 1, 2, 3, 2, 1, 0, 1].iter()
+                       .map(|&k| Some(k)).collect::<Vec<_>>());
+        // a single element is its own reflection
+        assert_eq!(neighbor(Border::Reflect, 0, -5, 1), Some(0));
+    }
+
+    #[test]
+    fn wrap() {
+        // bcd|abcd|abcd
+        assert_eq!(neighbors(Border::Wrap), [1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3].iter()
+                       .map(|&k| Some(k)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn zero() {
+        let mut expected = vec![None; 11];
+        for k in 0..4 {
+            expected[3 + k] = Some(k as u32);
+        }
+
+        assert_eq!(neighbors(Border::Zero), expected);
+    }
+
+    #[test]
+    fn separable() {
+        let g = [0.25f32, 0.5, 0.25];
+        let x = ::Mat::from_fn((4, 5), |(i, j)| (i * 5 + j) as f32);
+        let x = &*x;
+        let (mut dense, mut separable) = ([0f32; 20], [0f32; 20]);
+
+        for &border in &BORDERS {
+            ::Mat::reshape_mut(&mut dense, (4, 5))[..] = x.convolve(GAUSSIAN3, border);
+            ::Mat::reshape_mut(&mut separable, (4, 5))[..] = x.convolve_separable(g, g, border);
+
+            for (a, b) in dense.iter().zip(separable.iter()) {
+                assert!((a - b).abs() < 1e-5, "{:?}: {} vs {}", border, a, b);
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Cost model used to decide whether an assignment is parallelized
+//!
+//! The cost of an assignment is estimated per element of the output, in units of an `f32`
+//! addition, as the sum of:
+//!
+//! - the cost of each arithmetic operation in the expression, which depends on the element type
+//!   (see `Arith`). The operations are vectorized, so e.g. a `f64` addition costs twice as much as
+//!   a `f32` one because a SIMD register holds half as many `f64` lanes.
+//! - the cost of the built-in functions (see `math::Function`), `map` closures count as one
+//!   addition of their output type unless their cost is declared with `Math::map_with_cost`.
+//! - the memory traffic: the bytes read from the leaves plus the bytes written to the destination,
+//!   where moving 4 bytes costs as much as an `f32` addition.
+//!
+//! The total, multiplied by the number of elements, is compared against the threshold of the
+//! parallelism `Policy`.
+
+use std::{cmp, mem};
+
+use traits::Flops;
+
+/// Number of bytes that can be moved to/from memory in the time required to perform an `f32`
+/// addition
+const BYTES_PER_OP: usize = 4;
+
+/// Relative cost of the element-wise arithmetic operations on an element type, in units of an
+/// `f32` addition
+///
+/// The operations are vectorized, so by default their cost grows with the size of the element.
+/// Implemented for the primitives, where only the integer multiplication departs from the default.
+/// A user defined element type (e.g. complex numbers) opts in with an empty `impl`, and gets
+/// costed like a primitive of the same size.
+pub trait Arith: Sized {
+    /// Cost of an addition
+    #[inline(always)]
+    fn add() -> usize {
+        words::<Self>()
+    }
+
+    /// Cost of a comparison
+    #[inline(always)]
+    fn cmp() -> usize {
+        words::<Self>()
+    }
+
+    /// Cost of a multiplication
+    #[inline(always)]
+    fn mul() -> usize {
+        words::<Self>()
+    }
+}
+
+macro_rules! arith {
+    ($($ty:ty),+) => {
+        $(
+            impl Arith for $ty {}
+        )+
+    }
+}
+
+arith!(bool, f32, f64);
+
+// NB There's no SIMD instruction for 8-bit multiplication (the lanes are widened to 16 bits and
+// narrowed back), the 32-bit one has twice the latency of an addition, and the 64-bit one is
+// scalar on most x86_64 processors
+macro_rules! int {
+    ($($ty:ty),+) => {
+        $(
+            impl Arith for $ty {
+                #[inline(always)]
+                fn mul() -> usize {
+                    match mem::size_of::<$ty>() {
+                        1 | 4 => 2,
+                        2 => 1,
+                        _ => 8,
+                    }
+                }
+            }
+        )+
+    }
+}
+
+int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// Returns the size of `T` in units of an `f32`, at least one
+fn words<T>() -> usize {
+    cmp::max((mem::size_of::<T>() + 3) / 4, 1)
+}
+
+/// Returns the estimated cost of evaluating `nelems` elements of the expression `e` into a
+/// destination whose elements are of type `T`
+pub fn of<T, E>(e: &E, nelems: usize) -> usize where E: Flops {
+    let bytes = E::bytes() + mem::size_of::<T>();
+
+    (e.flops() + (bytes + BYTES_PER_OP - 1) / BYTES_PER_OP).saturating_mul(nelems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Arith;
+
+    /// A user defined element type, costed like a primitive of the same size
+    struct Complex { _re: f64, _im: f64 }
+
+    impl Arith for Complex {}
+
+    #[test]
+    fn mul() {
+        assert_eq!((f32::mul(), f64::mul()), (1, 2));
+        assert_eq!((i8::mul(), i16::mul(), i32::mul(), i64::mul()), (2, 1, 2, 8));
+        assert_eq!((u8::mul(), u16::mul(), u32::mul(), u64::mul()), (2, 1, 2, 8));
+        assert_eq!(Complex::mul(), 4);
+    }
+
+    #[test]
+    fn add() {
+        assert_eq!((bool::add(), u8::add(), f32::add(), f64::add()), (1, 1, 1, 2));
+        assert_eq!(Complex::add(), 4);
+    }
+}
@@ -0,0 +1,121 @@
+//! Errors reported by the `try_*` variants of the API
+
+use std::{error, fmt};
+
+/// An error
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Error {
+    /// One of the expressions of a fused assignment reads one of the destinations
+    Aliasing,
+    /// The slice can't be reshaped into a matrix of the requested size
+    BadReshape {
+        /// Length of the slice
+        len: usize,
+        /// The requested size
+        size: (u32, u32),
+    },
+    /// An element of a `checked_cast` doesn't fit in the destination type
+    Cast,
+    /// The index is out of the range of the matrix
+    OutOfRange {
+        /// The requested index
+        index: (u32, u32),
+        /// The size of the matrix
+        size: (u32, u32),
+    },
+    /// The number of elements of the matrix overflows `usize`, or the number of pixels of an image
+    /// overflows `u32`
+    Overflow,
+    /// The operands have different sizes
+    ShapeMismatch {
+        /// The size of the left operand (or of the destination)
+        lhs: (u32, u32),
+        /// The size of the right operand
+        rhs: (u32, u32),
+    },
+    /// The slice is too long to be viewed as a vector, whose length is an `u32`
+    TooLong {
+        /// Length of the slice
+        len: usize,
+    },
+}
+
+/// The result of the `try_*` operations
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Aliasing => "expression aliases the destination",
+            Error::BadReshape { .. } => "bad reshape",
+            Error::Cast => "conversion overflow",
+            Error::OutOfRange { .. } => "index out of range",
+            Error::Overflow => "size overflow",
+            Error::ShapeMismatch { .. } => "shape mismatch",
+            Error::TooLong { .. } => "slice too long",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Aliasing => f.write_str("expression aliases the destination"),
+            Error::BadReshape { len, size: (nrows, ncols) } => {
+                write!(f, "can't reshape a slice of length {} into a {}x{} matrix",
+                       len, nrows, ncols)
+            },
+            Error::Cast => f.write_str("an element doesn't fit in the destination type"),
+            Error::OutOfRange { index: (i, j), size: (nrows, ncols) } => {
+                write!(f, "index ({}, {}) out of range of a {}x{} matrix", i, j, nrows, ncols)
+            },
+            Error::Overflow => {
+                f.write_str("number of elements overflows usize, or number of pixels overflows u32")
+            },
+            Error::ShapeMismatch { lhs: (m, n), rhs: (p, q) } => {
+                write!(f, "shape mismatch: {}x{} vs {}x{}", m, n, p, q)
+            },
+            Error::TooLong { len } => {
+                write!(f, "can't view a slice of length {} as a vector", len)
+            },
+        }
+    }
+}
+
+/// Checks that the sizes of both operands match
+pub fn check_size(lhs: (u32, u32), rhs: (u32, u32)) -> Result<()> {
+    if lhs == rhs {
+        Ok(())
+    } else {
+        Err(Error::ShapeMismatch { lhs: lhs, rhs: rhs })
+    }
+}
+
+/// Checks that the size of `rhs`, unless it's a broadcasted scalar, matches `lhs`
+pub fn check_operand(lhs: (u32, u32), rhs: Option<(u32, u32)>) -> Result<()> {
+    match rhs {
+        None => Ok(()),
+        Some(rhs) => check_size(lhs, rhs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, check_operand, check_size};
+
+    #[test]
+    fn size() {
+        assert_eq!(check_size((2, 3), (2, 3)), Ok(()));
+        assert_eq!(check_size((2, 3), (3, 2)),
+                   Err(Error::ShapeMismatch { lhs: (2, 3), rhs: (3, 2) }));
+    }
+
+    #[test]
+    fn operand() {
+        // broadcasted scalars have no size, and match any operand
+        assert_eq!(check_operand((2, 3), None), Ok(()));
+        assert_eq!(check_operand((2, 3), Some((2, 3))), Ok(()));
+        assert_eq!(check_operand((2, 3), Some((2, 1))),
+                   Err(Error::ShapeMismatch { lhs: (2, 3), rhs: (2, 1) }));
+    }
+}
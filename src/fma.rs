@@ -0,0 +1,137 @@
+use std::{cmp, fmt};
+
+use cost::Arith;
+use kind::Broadcast;
+use math::MulAdd;
+use traits::{Describe, Flops, Layout, Matrix, Region, Shape, UnsafeGet, UnsafeGetLinear};
+
+impl<A, B, C> Describe for ::Fma<A, B, C> where
+    A: Describe + UnsafeGet,
+    B: Describe + UnsafeGet<Output=A::Output>,
+    C: Describe + UnsafeGet<Output=A::Output>,
+    A::Output: MulAdd,
+{
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(f.write_str("Fma("));
+        try!(self.0.fmt_tree(f));
+        try!(f.write_str(", "));
+        try!(self.1.fmt_tree(f));
+        try!(f.write_str(", "));
+        try!(self.2.fmt_tree(f));
+        f.write_str(")")
+    }
+}
+
+impl<A, B, C> Flops for ::Fma<A, B, C> where
+    A: Flops + UnsafeGet,
+    B: Flops + UnsafeGet<Output=A::Output>,
+    C: Flops + UnsafeGet<Output=A::Output>,
+    A::Output: Arith + MulAdd,
+{
+    #[inline(always)]
+    fn bytes() -> usize {
+        A::bytes() + B::bytes() + C::bytes()
+    }
+
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        let op = <A::Output as Arith>::mul() + <A::Output as Arith>::add();
+
+        self.0.flops() + self.1.flops() + self.2.flops() + op
+    }
+}
+
+impl<A, B, C> Layout for ::Fma<A, B, C> where
+    A: Layout + UnsafeGet,
+    B: Layout + UnsafeGet<Output=A::Output>,
+    C: Layout + UnsafeGet<Output=A::Output>,
+    A::Output: MulAdd,
+{
+    #[inline(always)]
+    fn aliases(&self, dst: &Region) -> bool {
+        self.0.aliases(dst) || self.1.aliases(dst) || self.2.aliases(dst)
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        cmp::max(self.0.col_stride(), cmp::max(self.1.col_stride(), self.2.col_stride()))
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        cmp::max(self.0.row_stride(), cmp::max(self.1.row_stride(), self.2.row_stride()))
+    }
+}
+
+// NB `a.mul_add(b, c)` is called on a matrix, `b` and `c` may be scalars, so the size is taken
+// from `a`
+impl<A, B, C> Matrix for ::Fma<A, B, C> where
+    A: Matrix + UnsafeGet,
+    B: UnsafeGet<Output=A::Output>,
+    C: UnsafeGet<Output=A::Output>,
+    A::Output: MulAdd,
+{
+    #[inline(always)]
+    fn nrows(&self) -> u32 {
+        A::nrows(&self.0)
+    }
+
+    #[inline(always)]
+    fn ncols(&self) -> u32 {
+        A::ncols(&self.0)
+    }
+
+    #[inline(always)]
+    fn nelems(&self) -> usize {
+        A::nelems(&self.0)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> (u32, u32) {
+        A::size(&self.0)
+    }
+}
+
+impl<A, B, C> Shape for ::Fma<A, B, C> where
+    A: Shape + UnsafeGet,
+    B: Shape + UnsafeGet<Output=A::Output>,
+    C: Shape + UnsafeGet<Output=A::Output>,
+    A::Output: MulAdd,
+    A::Kind: Broadcast<B::Kind>,
+    <A::Kind as Broadcast<B::Kind>>::Output: Broadcast<C::Kind>,
+{
+    type Kind = <<A::Kind as Broadcast<B::Kind>>::Output as Broadcast<C::Kind>>::Output;
+}
+
+impl<A, B, C> UnsafeGet for ::Fma<A, B, C> where
+    A: UnsafeGet,
+    B: UnsafeGet<Output=A::Output>,
+    C: UnsafeGet<Output=A::Output>,
+    A::Output: MulAdd,
+{
+    type Output = A::Output;
+
+    #[inline(always)]
+    unsafe fn unsafe_get(&self, i: (u32, u32)) -> A::Output {
+        self.0.unsafe_get(i).mul_add(self.1.unsafe_get(i), self.2.unsafe_get(i))
+    }
+}
+
+impl<A, B, C> UnsafeGetLinear for ::Fma<A, B, C> where
+    A: UnsafeGetLinear,
+    B: UnsafeGetLinear<Output=A::Output>,
+    C: UnsafeGetLinear<Output=A::Output>,
+    A::Output: MulAdd,
+{
+    #[inline(always)]
+    fn is_linear(&self, ncols: u32) -> bool {
+        self.0.is_linear(ncols) && self.1.is_linear(ncols) && self.2.is_linear(ncols)
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> A::Output {
+        let a = self.0.unsafe_get_linear(i);
+
+        a.mul_add(self.1.unsafe_get_linear(i), self.2.unsafe_get_linear(i))
+    }
+}
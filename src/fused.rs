@@ -0,0 +1,233 @@
+//! Multi-output fused assignment
+//!
+//! Assigning several expressions that share operands one by one requires a memory pass per
+//! output. `assign` evaluates all of them in a single (parallel) loop instead, so the leaves they
+//! have in common are read while they are still in cache.
+//!
+//! ``` ignore
+//! // Y, Cb and Cr channels from the R, G and B channels
+//! et::assign((&mut *y, &mut *cb, &mut *cr), (e1, e2, e3));
+//! ```
+//!
+//! The outputs are written one after the other in each iteration, so an expression must not read
+//! the other destinations, and may only read its own destination at the element being written.
+//! Such aliasing is detected and makes `assign` panic (`try_assign` returns an error instead).
+//!
+//! NB "Sharing" the leaves only means that they are still in cache when the next expression reads
+//! them, each expression reads its own leaves. Also, unlike `y[..] = expr`, the output is always
+//! split in static row stripes that are evaluated with 2D indexing: the linear, cache-tiled and
+//! dynamic schedule paths of the single output evaluator are not used, and the `schedule` of the
+//! policy is ignored.
+
+use std::{cmp, mem, slice};
+
+use cast::From;
+
+use check;
+use cost;
+use error::{self, Error, Result};
+use policy::{self, Policy};
+use pool::{self, Job};
+use stats::{self, Recorder};
+use traits::{Expr, Matrix, Region};
+
+/// The destination of an assignment
+pub trait Destination {
+    /// The element type
+    type Elem;
+    /// The shape kind of the destination
+    type Kind;
+
+    /// Returns the size of the destination, and a pointer to its elements in row major order
+    fn buffer(self) -> ((u32, u32), *mut Self::Elem);
+}
+
+impl<'a, T> Destination for &'a mut ::Col<T> {
+    type Elem = T;
+    type Kind = ::kind::Col;
+
+    fn buffer(self) -> ((u32, u32), *mut T) {
+        (self.size(), self.as_mut().as_mut_ptr())
+    }
+}
+
+impl<'a, T> Destination for &'a mut ::Mat<T> {
+    type Elem = T;
+    type Kind = ::kind::Mat;
+
+    fn buffer(self) -> ((u32, u32), *mut T) {
+        (self.size(), self.as_mut().as_mut_ptr())
+    }
+}
+
+/// A tuple of destinations that can be assigned a tuple of expressions in a single pass
+pub trait Fused<E> {
+    /// Evaluates the expressions `rhs` and stores the results in these destinations, using the
+    /// parallelism `policy`. Returns an error if the sizes don't match, or if an expression
+    /// aliases a destination.
+    fn try_assign_with(self, policy: Policy, rhs: E) -> Result<()>;
+}
+
+/// Evaluates the expressions `rhs` and stores the results in the destinations `dst`, in a single
+/// pass
+pub fn assign<D, E>(dst: D, rhs: E) where D: Fused<E> {
+    dst.try_assign_with(policy::get(), rhs).unwrap()
+}
+
+/// Same as `assign`, but uses the parallelism `policy` instead of the global one
+pub fn assign_with<D, E>(policy: Policy, dst: D, rhs: E) where D: Fused<E> {
+    dst.try_assign_with(policy, rhs).unwrap()
+}
+
+/// Same as `assign`, but returns an error instead of panicking
+pub fn try_assign<D, E>(dst: D, rhs: E) -> Result<()> where D: Fused<E> {
+    dst.try_assign_with(policy::get(), rhs)
+}
+
+/// A pointer that can be sent to the worker threads
+///
+/// The stripes of the destinations that each thread writes to don't overlap
+struct Ptr<T>(*mut T);
+
+impl<T> Clone for Ptr<T> {
+    fn clone(&self) -> Ptr<T> {
+        Ptr(self.0)
+    }
+}
+
+impl<T> Copy for Ptr<T> {}
+
+unsafe impl<T> Send for Ptr<T> where T: Send {}
+
+unsafe impl<T> Sync for Ptr<T> where T: Send {}
+
+macro_rules! fused {
+    ($(($($D:ident $E:ident $d:ident $e:ident),+))+) => {
+        $(
+            impl<$($D, $E),+> Fused<($($E,)+)> for ($($D,)+) where $(
+                $D: Destination,
+                $D::Elem: Send,
+                $E: Expr<Output=$D::Elem, Kind=$D::Kind> + Sync,
+            )+ {
+                fn try_assign_with(self, policy: Policy, rhs: ($($E,)+)) -> Result<()> {
+                    let ($($d,)+) = self;
+                    let ($($e,)+) = rhs;
+
+                    let mut regions = vec![];
+                    let mut sizes = vec![];
+                    $(
+                        let (size, $d) = $d.buffer();
+                        try!(error::check_size(size, $e.size()));
+                        sizes.push(size);
+
+                        let n = usize::from(size.0) * usize::from(size.1);
+                        regions.push(Region::of(unsafe { slice::from_raw_parts($d, n) }, size.1));
+
+                        let $d = Ptr($d);
+                     )+
+
+                    let mut j = 0;
+                    $(
+                        for (i, region) in regions.iter().enumerate() {
+                            let region = if i == j {
+                                *region
+                            } else {
+                                Region { strides: None, ..*region }
+                            };
+
+                            if $e.aliases(&region) {
+                                debug!("expression {} aliases destination {}", j, i);
+
+                                return Err(Error::Aliasing)
+                            }
+                        }
+
+                        j += 1;
+                     )+
+
+                    let (nrows, ncols) = sizes[0];
+                    for &size in &sizes[1..] {
+                        try!(error::check_size((nrows, ncols), size));
+                    }
+
+                    let nelems = usize::from(nrows) * usize::from(ncols);
+                    let cost = 0 $(+ cost::of::<$D::Elem, $E>(&$e, nelems))+;
+
+                    debug!("Estimated cost: {}", cost);
+
+                    if nelems == 0 {
+                        return Ok(())
+                    }
+
+                    let recorder = Recorder::start();
+                    let nthreads = policy.nthreads(cost);
+
+                    // NB all the expressions are evaluated together, so a failed check reports
+                    // all of them
+                    let mut expr = None;
+                    $(
+                        if let Some(e) = check::describe(&$e) {
+                            expr = Some(match expr {
+                                None => e,
+                                Some(prev) => format!("{}; {}", prev, e),
+                            })
+                        }
+                     )+
+
+                    {
+                        let recorder = recorder.as_ref();
+                        let expr = &expr;
+
+                        // Evaluates the rows `r0..r1` of all the outputs
+                        let run = |r0: u32, r1: u32| {
+                            let start = usize::from(r0) * usize::from(ncols);
+                            let n = usize::from(r1 - r0) * usize::from(ncols);
+
+                            stats::chunk(recorder, start, n, || check::within(expr, || unsafe {
+                                for i in r0..r1 {
+                                    let row = usize::from(i) * usize::from(ncols);
+
+                                    for j in 0..ncols {
+                                        let k = (row + usize::from(j)) as isize;
+
+                                        $(
+                                            *$d.0.offset(k) = $e.unsafe_get((i, j));
+                                         )+
+                                    }
+                                }
+                            }))
+                        };
+
+                        if nthreads == 1 {
+                            run(0, nrows)
+                        } else {
+                            debug!("Spinning up {} threads", nthreads);
+
+                            // rows per horizontal stripe
+                            let sz = (nrows - 1) / u32::from(nthreads).unwrap() + 1;
+                            let nstripes = (nrows - 1) / sz + 1;
+
+                            let run = &run;
+                            pool::get().execute((0..nstripes).map(move |s| {
+                                Box::new(move || {
+                                    run(s * sz, cmp::min((s + 1) * sz, nrows))
+                                }) as Job
+                            }).collect());
+                        }
+                    }
+
+                    let bytes = 0 $(+ ($E::bytes() + mem::size_of::<$D::Elem>()) * nelems)+;
+                    stats::finish(recorder, sizes.len() * nelems, bytes, cost, nthreads);
+
+                    Ok(())
+                }
+            }
+         )+
+    }
+}
+
+fused! {
+    (D1 E1 d1 e1, D2 E2 d2 e2)
+    (D1 E1 d1 e1, D2 E2 d2 e2, D3 E3 d3 e3)
+    (D1 E1 d1 e1, D2 E2 d2 e2, D3 E3 d3 e3, D4 E4 d4 e4)
+}
@@ -0,0 +1,183 @@
+//! Integration with the `image` crate
+//!
+//! Only available with the `image` feature, which is enabled by default.
+//!
+//! An `ImageBuffer` stores its pixels in row major order, with the channels of each pixel
+//! interleaved. `as_mat` views that buffer, without copying it, as a matrix with one row per pixel
+//! and one column per channel, so the columns of the matrix are the channels of the image. Single
+//! channel (`Luma`) images can also be viewed as a column vector with `as_col`.
+//!
+//! The `_mut` variants are used to write the result of an assignment into an existing image, and
+//! `from_mat`/`from_col` turn a matrix/vector back into an image without copying it.
+//!
+//! ``` ignore
+//! let rgb = image::open("rgb.jpg").unwrap().to_rgb();
+//! let (r, g, b) = et::image::rgb(&rgb);
+//!
+//! let mut gray = image::GrayImage::new(rgb.width(), rgb.height());
+//! et::image::as_col_mut(&mut gray)[..] = {
+//!     r.cast::<f32>() * 0.2126 +
+//!     g.cast::<f32>() * 0.7152 +
+//!     b.cast::<f32>() * 0.0722
+//! }.rounding_cast::<u8>();
+//! ```
+
+extern crate image;
+
+use std::ops::{Deref, DerefMut};
+
+use cast::From;
+
+use self::image::{ImageBuffer, Luma, Pixel, Rgb, Rgba};
+
+use error::{self, Error, Result};
+use traits::Matrix;
+
+/// Views the pixels of `img` as a matrix with one row per pixel and one column per channel
+pub fn as_mat<P, C>(img: &ImageBuffer<P, C>) -> &::Mat<P::Subpixel> where
+    P: Pixel + 'static,
+    C: Deref<Target=[P::Subpixel]>,
+{
+    try_as_mat(img).unwrap()
+}
+
+/// Views the pixels of `img` as a mutable matrix with one row per pixel and one column per channel
+pub fn as_mat_mut<P, C>(img: &mut ImageBuffer<P, C>) -> &mut ::Mat<P::Subpixel> where
+    P: Pixel + 'static,
+    C: Deref<Target=[P::Subpixel]> + DerefMut,
+{
+    try_as_mat_mut(img).unwrap()
+}
+
+/// Views the pixels of a single channel image as a column vector
+pub fn as_col<T, C>(img: &ImageBuffer<Luma<T>, C>) -> &::Col<T> where
+    C: Deref<Target=[T]>,
+    Luma<T>: Pixel<Subpixel=T> + 'static,
+{
+    try_as_col(img).unwrap()
+}
+
+/// Views the pixels of a single channel image as a mutable column vector
+pub fn as_col_mut<T, C>(img: &mut ImageBuffer<Luma<T>, C>) -> &mut ::Col<T> where
+    C: Deref<Target=[T]> + DerefMut,
+    Luma<T>: Pixel<Subpixel=T> + 'static,
+{
+    try_as_col_mut(img).unwrap()
+}
+
+/// Turns a column vector back into a `width` x `height` single channel image, without copying it
+pub fn from_col<T>(col: &::Col<T>, dimensions: (u32, u32)) -> ImageBuffer<Luma<T>, &[T]> where
+    Luma<T>: Pixel<Subpixel=T> + 'static,
+{
+    try_from_col(col, dimensions).unwrap()
+}
+
+/// Turns a matrix with one row per pixel and one column per channel back into a `width` x
+/// `height` image, without copying it
+pub fn from_mat<P>(mat: &::Mat<P::Subpixel>, dimensions: (u32, u32))
+                   -> ImageBuffer<P, &[P::Subpixel]> where
+    P: Pixel + 'static,
+{
+    try_from_mat(mat, dimensions).unwrap()
+}
+
+/// Returns views into the red, green and blue channels of `img`
+pub fn rgb<T, C>(img: &ImageBuffer<Rgb<T>, C>)
+                 -> (&::strided::Col<T>, &::strided::Col<T>, &::strided::Col<T>) where
+    C: Deref<Target=[T]>,
+    Rgb<T>: Pixel<Subpixel=T> + 'static,
+{
+    let mat = as_mat(img);
+
+    (&mat[.., 0], &mat[.., 1], &mat[.., 2])
+}
+
+/// Returns views into the red, green, blue and alpha channels of `img`
+pub fn rgba<T, C>(img: &ImageBuffer<Rgba<T>, C>) -> (&::strided::Col<T>,
+                                                     &::strided::Col<T>,
+                                                     &::strided::Col<T>,
+                                                     &::strided::Col<T>) where
+    C: Deref<Target=[T]>,
+    Rgba<T>: Pixel<Subpixel=T> + 'static,
+{
+    let mat = as_mat(img);
+
+    (&mat[.., 0], &mat[.., 1], &mat[.., 2], &mat[.., 3])
+}
+
+/// Same as `as_col`, but returns an error if the number of pixels overflows `u32`
+pub fn try_as_col<T, C>(img: &ImageBuffer<Luma<T>, C>) -> Result<&::Col<T>> where
+    C: Deref<Target=[T]>,
+    Luma<T>: Pixel<Subpixel=T> + 'static,
+{
+    let n = try!(npixels(img.dimensions()));
+
+    ::Col::try_from_slice(&img[..usize::from(n)])
+}
+
+/// Same as `as_col_mut`, but returns an error if the number of pixels overflows `u32`
+pub fn try_as_col_mut<T, C>(img: &mut ImageBuffer<Luma<T>, C>) -> Result<&mut ::Col<T>> where
+    C: Deref<Target=[T]> + DerefMut,
+    Luma<T>: Pixel<Subpixel=T> + 'static,
+{
+    let n = try!(npixels(img.dimensions()));
+
+    ::Col::try_from_slice_mut(&mut img[..usize::from(n)])
+}
+
+/// Same as `as_mat`, but returns an error if the number of pixels overflows `u32`
+pub fn try_as_mat<P, C>(img: &ImageBuffer<P, C>) -> Result<&::Mat<P::Subpixel>> where
+    P: Pixel + 'static,
+    C: Deref<Target=[P::Subpixel]>,
+{
+    let size = try!(size::<P>(img.dimensions()));
+    let n = try!(::mat::nelems(size));
+
+    // NB The container may be longer than the image
+    ::Mat::try_reshape(&img[..n], size)
+}
+
+/// Same as `as_mat_mut`, but returns an error if the number of pixels overflows `u32`
+pub fn try_as_mat_mut<P, C>(img: &mut ImageBuffer<P, C>) -> Result<&mut ::Mat<P::Subpixel>> where
+    P: Pixel + 'static,
+    C: Deref<Target=[P::Subpixel]> + DerefMut,
+{
+    let size = try!(size::<P>(img.dimensions()));
+    let n = try!(::mat::nelems(size));
+
+    ::Mat::try_reshape_mut(&mut img[..n], size)
+}
+
+/// Same as `from_col`, but returns an error if the length of the vector doesn't match the size
+/// of the image
+pub fn try_from_col<T>(col: &::Col<T>, dimensions: (u32, u32))
+                       -> Result<ImageBuffer<Luma<T>, &[T]>> where
+    Luma<T>: Pixel<Subpixel=T> + 'static,
+{
+    let (width, height) = dimensions;
+    try!(error::check_size((try!(npixels(dimensions)), 1), col.size()));
+
+    Ok(ImageBuffer::from_raw(width, height, col.as_ref()).unwrap())
+}
+
+/// Same as `from_mat`, but returns an error if the size of the matrix doesn't match the size of
+/// the image
+pub fn try_from_mat<P>(mat: &::Mat<P::Subpixel>, dimensions: (u32, u32))
+                       -> Result<ImageBuffer<P, &[P::Subpixel]>> where
+    P: Pixel + 'static,
+{
+    let (width, height) = dimensions;
+    try!(error::check_size(try!(size::<P>(dimensions)), mat.size()));
+
+    Ok(ImageBuffer::from_raw(width, height, mat.as_ref()).unwrap())
+}
+
+/// Returns the number of pixels of a `width` x `height` image
+fn npixels((width, height): (u32, u32)) -> Result<u32> {
+    width.checked_mul(height).ok_or(Error::Overflow)
+}
+
+/// Returns the size of the matrix that holds the pixels of a `width` x `height` image
+fn size<P>(dimensions: (u32, u32)) -> Result<(u32, u32)> where P: Pixel {
+    Ok((try!(npixels(dimensions)), u32::from(P::channel_count())))
+}
@@ -0,0 +1,115 @@
+//! Convolution kernels, see `Stencil::convolve`
+//!
+//! The size of a kernel is part of its type: square kernels are arrays of rows (`[[T; N]; N]`), and
+//! 1D kernels (`[T; N]`) filter along the rows. This lets the cost model (see the `cost` module)
+//! account for the size of the kernel, so large filters get parallelized.
+//!
+//! ``` ignore
+//! use et::{Border, Stencil};
+//! use et::kernel::{GAUSSIAN3, SOBEL_X};
+//!
+//! blurred[..] = img.convolve(GAUSSIAN3, Border::Reflect);
+//! edges[..] = img.convolve(SOBEL_X, Border::Clamp).abs();
+//! ```
+
+use std::num::Zero;
+use std::ops::{Add, Mul};
+
+/// A convolution kernel with a fixed size
+pub trait Kernel {
+    /// The type of the coefficients
+    type Elem;
+
+    /// Returns the number of rows and columns of the kernel
+    fn size() -> (u32, u32);
+
+    /// Returns the number of multiply-adds required to apply the kernel once
+    fn taps() -> usize;
+
+    /// Returns the weighted sum of a neighborhood, where `x(r, c)` is the neighbor that lines up
+    /// with the coefficient `(r, c)` of the kernel
+    fn apply<F>(&self, x: F) -> Self::Elem where F: Fn(u32, u32) -> Self::Elem;
+}
+
+macro_rules! kernel {
+    ($($n:expr),+) => {
+        $(
+            /// A 1D (`1xN`) kernel
+            impl<T> Kernel for [T; $n] where T: Add<Output=T> + Copy + Mul<Output=T> + Zero {
+                type Elem = T;
+
+                #[inline(always)]
+                fn size() -> (u32, u32) {
+                    (1, $n)
+                }
+
+                #[inline(always)]
+                fn taps() -> usize {
+                    $n
+                }
+
+                #[inline(always)]
+                fn apply<F>(&self, x: F) -> T where F: Fn(u32, u32) -> T {
+                    let mut sum = T::zero();
+
+                    for (c, &k) in self.iter().enumerate() {
+                        sum = sum + k * x(0, c as u32);
+                    }
+
+                    sum
+                }
+            }
+
+            /// A dense, square, kernel
+            impl<T> Kernel for [[T; $n]; $n] where
+                T: Add<Output=T> + Copy + Mul<Output=T> + Zero,
+            {
+                type Elem = T;
+
+                #[inline(always)]
+                fn size() -> (u32, u32) {
+                    ($n, $n)
+                }
+
+                #[inline(always)]
+                fn taps() -> usize {
+                    $n * $n
+                }
+
+                #[inline(always)]
+                fn apply<F>(&self, x: F) -> T where F: Fn(u32, u32) -> T {
+                    let mut sum = T::zero();
+
+                    for (r, row) in self.iter().enumerate() {
+                        for (c, &k) in row.iter().enumerate() {
+                            sum = sum + k * x(r as u32, c as u32);
+                        }
+                    }
+
+                    sum
+                }
+            }
+         )+
+    }
+}
+
+kernel!(1, 2, 3, 4, 5, 6, 7, 8, 9, 11, 13, 15);
+
+/// 3x3 box blur
+pub const BOX3: [[f32; 3]; 3] = [[1. / 9.; 3]; 3];
+
+/// 3x3 gaussian blur
+pub const GAUSSIAN3: [[f32; 3]; 3] = [
+    [1. / 16., 2. / 16., 1. / 16.],
+    [2. / 16., 4. / 16., 2. / 16.],
+    [1. / 16., 2. / 16., 1. / 16.],
+];
+
+/// 3x3 Laplacian
+pub const LAPLACIAN3: [[f32; 3]; 3] = [[0., 1., 0.], [1., -4., 1.], [0., 1., 0.]];
+
+/// Horizontal Sobel operator, responds to vertical edges
+pub const SOBEL_X: [[f32; 3]; 3] = [[-1., 0., 1.], [-2., 0., 2.], [-1., 0., 1.]];
+
+/// Vertical Sobel operator, responds to horizontal edges
+pub const SOBEL_Y: [[f32; 3]; 3] = [[-1., -2., -1.], [0., 0., 0.], [1., 2., 1.]];
@@ -0,0 +1,62 @@
+//! Shape kinds
+//!
+//! Every expression has a shape kind (see `Shape`): a column vector, a matrix, or a scalar that's
+//! broadcasted to the shape of the other operands. The kinds of the operands must be compatible,
+//! and the kind of the expression must match the kind of the destination, otherwise the program is
+//! rejected at compile time. For example, `Mat[..] = &Col + &Col` doesn't compile.
+//!
+//! The number of rows and columns are still checked at run time.
+
+/// Column vector
+pub enum Col {}
+
+/// Matrix
+pub enum Mat {}
+
+/// Scalar, broadcasted to the shape of the other operands
+pub enum Scalar {}
+
+/// The kind of the result of an element-wise operation between operands of kinds `Self` and `K`
+pub trait Broadcast<K> {
+    /// The kind of the result
+    type Output;
+}
+
+impl<K> Broadcast<K> for Scalar {
+    type Output = K;
+}
+
+impl Broadcast<Col> for Col {
+    type Output = Col;
+}
+
+impl Broadcast<Scalar> for Col {
+    type Output = Col;
+}
+
+impl Broadcast<Mat> for Mat {
+    type Output = Mat;
+}
+
+impl Broadcast<Scalar> for Mat {
+    type Output = Mat;
+}
+
+/// The kind of the transpose of an expression of kind `Self`
+pub trait Transposed {
+    /// The kind of the transpose
+    type Output;
+}
+
+// NB The transpose of a column vector is a row vector, i.e. a `1 x n` matrix
+impl Transposed for Col {
+    type Output = Mat;
+}
+
+impl Transposed for Mat {
+    type Output = Mat;
+}
+
+impl Transposed for Scalar {
+    type Output = Scalar;
+}
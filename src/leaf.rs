@@ -0,0 +1,174 @@
+//! User defined leaves
+//!
+//! The accessors that the evaluator uses to read the leaves of an expression skip the bounds
+//! checks, so they are not exposed. Instead, a new kind of leaf (e.g. a matrix stored in another
+//! crate, or a procedurally generated one) implements the safe `Leaf` trait and gets wrapped with
+//! `leaf`. The evaluator only calls `Leaf::get` with coordinates that are in range of the size the
+//! leaf had when it was wrapped.
+//!
+//! ``` ignore
+//! struct Ramp(u32);
+//!
+//! impl et::Leaf for Ramp {
+//!     type Elem = f32;
+//!     type Kind = et::kind::Col;
+//!
+//!     fn get(&self, (i, _): (u32, u32)) -> f32 {
+//!         i as f32
+//!     }
+//!
+//!     fn size(&self) -> (u32, u32) {
+//!         (self.0, 1)
+//!     }
+//! }
+//!
+//! y[..] = et::leaf(Ramp(y.nrows())).sqrt();
+//! ```
+
+use std::fmt;
+
+use cast::From;
+
+use traits::{self, Describe, Flops, Layout, Matrix, Region, Shape, UnsafeGet, UnsafeGetLinear};
+
+/// A leaf of an expression, defined outside this crate
+pub trait Leaf {
+    /// The element type
+    type Elem;
+    /// The shape kind of the leaf, `kind::Col` or `kind::Mat`
+    type Kind;
+
+    /// Returns the cost of computing one element, in units of an `f32` addition
+    fn flops(&self) -> usize {
+        0
+    }
+
+    /// Returns the element at the coordinates `(i, j)`
+    ///
+    /// NB `i` and `j` are always in the range given by `size`
+    fn get(&self, (u32, u32)) -> Self::Elem;
+
+    /// Returns the number of rows and columns of the leaf
+    ///
+    /// NB This is only called once, by `leaf`, the leaf must not change size afterwards
+    fn size(&self) -> (u32, u32);
+}
+
+/// Wraps `l` so it can be used as a leaf of a lazy expression
+///
+/// NB `l.size()` is called here, and only here
+pub fn leaf<L>(l: L) -> ::Custom<L> where L: Leaf {
+    let size = l.size();
+
+    ::Custom(l, size)
+}
+
+impl<L> Describe for ::Custom<L> where L: Leaf {
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (nrows, ncols) = self.1;
+
+        write!(f, "{}[{}x{}]", traits::type_name::<L>(), nrows, ncols)
+    }
+}
+
+impl<L> Flops for ::Custom<L> where L: Leaf {
+    #[inline(always)]
+    fn bytes() -> usize {
+        0
+    }
+
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        self.0.flops()
+    }
+}
+
+// NB A leaf can't read the destination of the assignment: it only has shared access to its data,
+// and the destination is mutably borrowed for the duration of the assignment
+impl<L> Layout for ::Custom<L> where L: Leaf {
+    fn aliases(&self, _: &Region) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        0
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        0
+    }
+}
+
+impl<L> Matrix for ::Custom<L> where L: Leaf {
+    #[inline(always)]
+    fn size(&self) -> (u32, u32) {
+        self.1
+    }
+}
+
+impl<L> Shape for ::Custom<L> where L: Leaf {
+    type Kind = L::Kind;
+}
+
+impl<L> UnsafeGet for ::Custom<L> where L: Leaf {
+    type Output = L::Elem;
+
+    #[inline(always)]
+    unsafe fn unsafe_get(&self, (i, j): (u32, u32)) -> L::Elem {
+        self.0.get((i, j))
+    }
+}
+
+// NB The elements are computed from their coordinates, so this falls back to 2D indexing
+impl<L> UnsafeGetLinear for ::Custom<L> where L: Leaf {
+    #[inline(always)]
+    fn is_linear(&self, _: u32) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> L::Elem {
+        let ncols = usize::from((self.1).1);
+        let (r, c) = (i / ncols, i % ncols);
+
+        self.0.get((r as u32, c as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use traits::Matrix;
+
+    use super::{Leaf, leaf};
+
+    /// A leaf that grows every time it's asked for its size
+    struct Growing(Cell<u32>);
+
+    impl Leaf for Growing {
+        type Elem = f32;
+        type Kind = ::kind::Col;
+
+        fn get(&self, (i, _): (u32, u32)) -> f32 {
+            i as f32
+        }
+
+        fn size(&self) -> (u32, u32) {
+            let n = self.0.get();
+            self.0.set(n + 1);
+            (n, 1)
+        }
+    }
+
+    #[test]
+    fn size_is_queried_once() {
+        let l = leaf(Growing(Cell::new(2)));
+
+        assert_eq!(l.size(), (2, 1));
+        assert_eq!(l.size(), (2, 1));
+        assert_eq!((l.0).0.get(), 3);
+    }
+}
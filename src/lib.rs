@@ -0,0 +1,182 @@
+//! Linear algebra with expression templates
+
+//#![deny(missing_docs)]
+
+#![feature(asm)]
+#![feature(core)]
+#![feature(filling_drop)]
+#![feature(indexed_assignment)]
+#![feature(unboxed_closures)]
+#![feature(unsized_types)]
+#![feature(zero_one)]
+
+extern crate cast;
+extern crate num_cpus;
+extern crate time;
+
+#[macro_use]
+extern crate log;
+
+use std::marker::PhantomData;
+
+use traits::UnsafeGet;
+
+mod add;
+mod check;
+mod col;
+mod compare;
+mod convolve;
+mod error;
+mod fma;
+mod fused;
+mod leaf;
+mod map;
+mod mat;
+mod mul;
+mod ops;
+mod pool;
+mod raw;
+mod shift;
+mod sub;
+mod traits;
+mod transpose;
+
+pub mod calibrate;
+pub mod color;
+pub mod convert;
+pub mod cost;
+pub mod kernel;
+#[cfg(feature = "image")]
+pub mod image;
+pub mod kind;
+pub mod math;
+pub mod policy;
+pub mod simd;
+pub mod stats;
+pub mod strided;
+
+pub use compare::{Compare, select, try_select};
+pub use convert::Convert;
+pub use convolve::Stencil;
+pub use error::{Error, Result};
+pub use fused::{assign, assign_with, try_assign};
+pub use leaf::{Leaf, leaf};
+pub use math::Math;
+pub use policy::Policy;
+pub use traits::{Expr, Matrix};
+
+// Benchmarks with synthetic inputs, `cargo run --release -- bench`
+// NB Part of the library because the baselines build the lazy nodes directly
+#[doc(hidden)]
+pub mod bench;
+
+pub unsized type Col<T> = ::raw::Slice<T>;
+
+pub unsized type Mat<T> = ::raw::Mat<T>;
+
+// NB Scalars will always be placed in the leftmost side of a lazy sum, for example the following
+// expression: `x + 1. + y + 2.` will be reduced as follows:
+// - `Sum<1., x> + y + 2.`
+// - `Sum<1., Sum<x, y>> + 2.`
+// - `Sum<3., Sum<x, y>>`
+/// Lazy addition
+pub struct Add<A, B>(A, B) where
+    A: UnsafeGet,
+    B: UnsafeGet,
+    A::Output: std::ops::Add<B::Output>;
+
+/// Lazy application of a built-in (or user provided) math function with a known cost
+pub struct Apply<F, M>(F, M) where
+    F: math::Function<M::Output>,
+    M: UnsafeGet;
+
+/// How the neighbors that fall outside of an operand are read, see `Stencil`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Border {
+    /// The nearest element is repeated, `aa|abcd|dd`
+    Clamp,
+    /// The elements are mirrored around the edge, `cb|abcd|cb`
+    Reflect,
+    /// The elements wrap around, `cd|abcd|ab`
+    Wrap,
+    /// The neighbors are zero, `00|abcd|00`
+    Zero,
+}
+
+/// Lazy 2D convolution with a fixed size kernel
+pub struct Convolve<M, K>(M, K, Border) where
+    M: UnsafeGet,
+    K: kernel::Kernel<Elem=M::Output>;
+
+/// A user defined leaf, see `Leaf`
+///
+/// NB The size is queried once, when the leaf is wrapped. `Leaf::size` is safe code that could
+/// return something else on the next call, and the evaluator relies on the size it checked
+pub struct Custom<L>(L, (u32, u32)) where L: Leaf;
+
+/// Lazy element-wise comparison, yields a `bool` per element
+pub struct Cmp<A, B>(A, B, CmpOp) where
+    A: UnsafeGet,
+    B: UnsafeGet,
+    A::Output: PartialOrd<B::Output>;
+
+/// The comparison performed by a `Cmp` proxy
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CmpOp {
+    /// `a == b`
+    Eq,
+    /// `a >= b`
+    Ge,
+    /// `a > b`
+    Gt,
+    /// `a <= b`
+    Le,
+    /// `a < b`
+    Lt,
+    /// `a != b`
+    Ne,
+}
+
+/// Lazy fused multiply-add, `a * b + c` rounded once, see `Math::mul_add`
+pub struct Fma<A, B, C>(A, B, C) where
+    A: UnsafeGet,
+    B: UnsafeGet<Output=A::Output>,
+    C: UnsafeGet<Output=A::Output>,
+    A::Output: math::MulAdd;
+
+/// Lazy function application
+pub struct Map<F, M>(F, M) where
+    F: Fn<(M::Output,)>,
+    M: UnsafeGet;
+
+// NB Same as above, the scalars will be placed in the leftmost side of a lazy product
+/// Lazy element-wise multiplication
+pub struct Mul<A, B>(A, B) where
+    A: UnsafeGet,
+    B: UnsafeGet,
+    A::Output: std::ops::Mul<B::Output>;
+
+/// Lazy element-wise selection: picks the element of `A` where the mask is `true`, and the element
+/// of `B` otherwise
+pub struct Select<M, A, B>(M, A, B) where
+    M: UnsafeGet<Output=bool>,
+    A: UnsafeGet,
+    B: UnsafeGet<Output=A::Output>;
+
+/// Lazy column pass of a separable convolution over the (owned) result of its row pass, see
+/// `Stencil::convolve_separable`
+pub struct Separable<T, R, C>(Box<Mat<T>>, C, Border, PhantomData<R>) where
+    C: kernel::Kernel<Elem=T>;
+
+/// Lazily shifted view: the element `(i, j)` is the element `(i + dr, j + dc)` of the operand, see
+/// `Stencil::shift`
+pub struct Shift<M>(M, (i32, i32), Border) where M: UnsafeGet;
+
+/// Lazy element-wise subtraction
+pub struct Sub<A, B>(A, B) where
+    A: UnsafeGet,
+    B: UnsafeGet,
+    A::Output: std::ops::Sub<B::Output>;
+
+/// Lazy transposition
+pub struct Transpose<M>(M) where M: UnsafeGet;
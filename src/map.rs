@@ -1,12 +1,53 @@
-use traits::{Flops, Matrix, UnsafeGet};
+use std::fmt;
 
+use cost::Arith;
+use traits::{Describe, Flops, Layout, Matrix, Region, Shape, UnsafeGet, UnsafeGetLinear};
+
+impl<F, M> Describe for ::Map<F, M> where
+    F: Fn<(M::Output,)>,
+    M: Describe + UnsafeGet,
+{
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(f.write_str("Map(<fn>, "));
+        try!(self.1.fmt_tree(f));
+        f.write_str(")")
+    }
+}
+
+// NB The closure is costed as an addition of its output type
 impl<F, M> Flops for ::Map<F, M> where
     F: Fn<(M::Output,)>,
+    F::Output: Arith,
     M: Flops + Matrix + UnsafeGet,
 {
     #[inline(always)]
-    fn flops() -> usize {
-        M::flops() + 1
+    fn bytes() -> usize {
+        M::bytes()
+    }
+
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        self.1.flops() + <F::Output as Arith>::add()
+    }
+}
+
+impl<F, M> Layout for ::Map<F, M> where
+    F: Fn<(M::Output,)>,
+    M: Layout + UnsafeGet,
+{
+    #[inline(always)]
+    fn aliases(&self, dst: &Region) -> bool {
+        self.1.aliases(dst)
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        self.1.col_stride()
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        self.1.row_stride()
     }
 }
 
@@ -35,6 +76,13 @@ impl<F, M> Matrix for ::Map<F, M> where
     }
 }
 
+impl<F, M> Shape for ::Map<F, M> where
+    F: Fn<(M::Output,)>,
+    M: Shape + UnsafeGet,
+{
+    type Kind = M::Kind;
+}
+
 impl<F, M> UnsafeGet for ::Map<F, M> where
     F: Fn<(M::Output,)>,
     M: UnsafeGet,
@@ -46,3 +94,18 @@ impl<F, M> UnsafeGet for ::Map<F, M> where
         self.0(self.1.unsafe_get(i))
     }
 }
+
+impl<F, M> UnsafeGetLinear for ::Map<F, M> where
+    F: Fn<(M::Output,)>,
+    M: UnsafeGetLinear,
+{
+    #[inline(always)]
+    fn is_linear(&self, ncols: u32) -> bool {
+        self.1.is_linear(ncols)
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> F::Output {
+        self.0(self.1.unsafe_get_linear(i))
+    }
+}
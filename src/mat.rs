@@ -4,27 +4,77 @@ use std::{fmt, iter, mem, slice};
 
 use cast::From;
 
-use traits::{Flops, Matrix, UnsafeGet};
+use check;
+use error::{Error, Result};
+use traits::{Describe, Flops, Layout, Matrix, Region, Shape, UnsafeGet, UnsafeGetLinear};
+
+/// Returns the number of elements of a matrix of the given size
+pub fn nelems((nrows, ncols): (u32, u32)) -> Result<usize> {
+    usize::from(nrows).checked_mul(usize::from(ncols)).ok_or(Error::Overflow)
+}
 
 impl<T> ::Mat<T> {
     /// Creates a matrix where each element is initialized to `elem`
-    pub fn from_elem((nrows, ncols): (u32, u32), elem: T) -> Box<::Mat<T>> where T: Clone {
-        let n = usize::from(nrows).checked_mul(usize::from(ncols)).unwrap();
+    pub fn from_elem(size: (u32, u32), elem: T) -> Box<::Mat<T>> where T: Clone {
+        ::Mat::try_from_elem(size, elem).unwrap()
+    }
+
+    /// Creates a matrix where each element is initialized using the function `f`
+    pub fn from_fn<F>(size: (u32, u32), f: F) -> Box<::Mat<T>> where
+        F: FnMut((u32, u32)) -> T,
+    {
+        ::Mat::try_from_fn(size, f).unwrap()
+    }
+
+    /// Reshapes an slice into a matrix with dimensions `(nrows, ncols)`
+    pub fn reshape(slice: &[T], size: (u32, u32)) -> &::Mat<T> {
+        ::Mat::try_reshape(slice, size).unwrap()
+    }
+
+    /// Reshapes a mutable slice into a matrix with dimensions `(nrows, ncols)`
+    pub fn reshape_mut(slice: &mut [T], size: (u32, u32)) -> &mut ::Mat<T> {
+        ::Mat::try_reshape_mut(slice, size).unwrap()
+    }
+
+    /// Returns a view into the column `c` of this matrix, or an error if `c` is out of range
+    pub fn try_col(&self, c: u32) -> Result<&::strided::Col<T>> {
+        unsafe {
+            let ::raw::Mat { data, nrows, ncols } = self.repr();
+
+            if c >= ncols {
+                return Err(Error::OutOfRange { index: (0, c), size: (nrows, ncols) })
+            }
+
+            Ok(mem::transmute(::strided::raw::Slice {
+                data: data.offset(c as isize),
+                len: nrows,
+                stride: ncols,
+                // NB the column may be read (and checked) as part of the whole matrix
+                alloc: self.nelems().saturating_sub(usize::from(c)),
+            }))
+        }
+    }
+
+    /// Same as `from_elem`, but returns an error if the number of elements overflows `usize`
+    pub fn try_from_elem((nrows, ncols): (u32, u32), elem: T) -> Result<Box<::Mat<T>>> where
+        T: Clone,
+    {
+        let n = try!(nelems((nrows, ncols)));
         let mut v: Vec<_> = iter::repeat(elem).take(n).collect();
 
         let data = v.as_mut_ptr();
         mem::forget(v);
 
         unsafe {
-            mem::transmute(::raw::Mat { data: data, nrows: nrows, ncols: ncols })
+            Ok(mem::transmute(::raw::Mat { data: data, nrows: nrows, ncols: ncols }))
         }
     }
 
-    /// Creates a matrix where each element is initialized using the function `f`
-    pub fn from_fn<F>((nrows, ncols): (u32, u32), mut f: F) -> Box<::Mat<T>> where
+    /// Same as `from_fn`, but returns an error if the number of elements overflows `usize`
+    pub fn try_from_fn<F>((nrows, ncols): (u32, u32), mut f: F) -> Result<Box<::Mat<T>>> where
         F: FnMut((u32, u32)) -> T,
     {
-        let n = usize::from(nrows).checked_mul(usize::from(ncols)).unwrap();
+        let n = try!(nelems((nrows, ncols)));
         let mut v = Vec::with_capacity(n);
 
         for i in 0..nrows {
@@ -37,23 +87,52 @@ impl<T> ::Mat<T> {
         mem::forget(v);
 
         unsafe {
-            mem::transmute(::raw::Mat { data: data, nrows: nrows, ncols: ncols })
+            Ok(mem::transmute(::raw::Mat { data: data, nrows: nrows, ncols: ncols }))
         }
     }
 
-    /// Reshapes an slice into a matrix with dimensions `(nrows, ncols)`
-    pub fn reshape(slice: &[T], (nrows, ncols): (u32, u32)) -> &::Mat<T> {
-        unsafe {
-            assert_eq!(slice.len(), usize::from(nrows) * usize::from(ncols));
+    /// Same as `ones`, but returns an error if the number of elements overflows `usize`
+    pub fn try_ones(size: (u32, u32)) -> Result<Box<::Mat<T>>> where T: Clone + One {
+        ::Mat::try_from_elem(size, T::one())
+    }
+
+    /// Same as `reshape`, but returns an error if the length of the slice doesn't match the
+    /// requested size
+    pub fn try_reshape(slice: &[T], (nrows, ncols): (u32, u32)) -> Result<&::Mat<T>> {
+        if nelems((nrows, ncols)) != Ok(slice.len()) {
+            return Err(Error::BadReshape { len: slice.len(), size: (nrows, ncols) })
+        }
 
-            mem::transmute(::raw::Mat {
+        unsafe {
+            Ok(mem::transmute(::raw::Mat {
                 data: slice.as_ptr() as *mut T,
                 nrows: nrows,
                 ncols: ncols,
-            })
+            }))
+        }
+    }
+
+    /// Same as `reshape_mut`, but returns an error if the length of the slice doesn't match the
+    /// requested size
+    pub fn try_reshape_mut(slice: &mut [T], (nrows, ncols): (u32, u32)) -> Result<&mut ::Mat<T>> {
+        if nelems((nrows, ncols)) != Ok(slice.len()) {
+            return Err(Error::BadReshape { len: slice.len(), size: (nrows, ncols) })
+        }
+
+        unsafe {
+            Ok(mem::transmute(::raw::Mat {
+                data: slice.as_mut_ptr(),
+                nrows: nrows,
+                ncols: ncols,
+            }))
         }
     }
 
+    /// Same as `zeros`, but returns an error if the number of elements overflows `usize`
+    pub fn try_zeros(size: (u32, u32)) -> Result<Box<::Mat<T>>> where T: Clone + Zero {
+        ::Mat::try_from_elem(size, T::zero())
+    }
+
     /// Lazily maps this matrix
     pub fn map<F>(&self, f: F) -> ::Map<F, &::Mat<T>> where
         F: Fn<(T,)>,
@@ -74,6 +153,11 @@ impl<T> ::Mat<T> {
         }
     }
 
+    /// Lazily transposes this matrix
+    pub fn t(&self) -> ::Transpose<&::Mat<T>> where T: Clone {
+        ::Transpose(self)
+    }
+
     /// Creates a matrix filled with zeros
     pub fn zeros((nrows, ncols): (u32, u32)) -> Box<::Mat<T>> where T: Clone + Zero {
         ::Mat::from_elem((nrows, ncols), T::zero())
@@ -132,8 +216,18 @@ impl<T> fmt::Debug for ::Mat<T> where T: fmt::Debug {
     }
 }
 
+impl<T> Describe for ::Mat<T> {
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Mat[{}x{}]", self.nrows(), self.ncols())
+    }
+}
+
 impl<T> Flops for ::Mat<T> {
-    fn flops() -> usize {
+    fn bytes() -> usize {
+        mem::size_of::<T>()
+    }
+
+    fn flops(&self) -> usize {
         0
     }
 }
@@ -142,17 +236,27 @@ impl<T> Index<(RangeFull, u32)> for ::Mat<T> {
     type Output = ::strided::Col<T>;
 
     fn index(&self, (_, c): (RangeFull, u32)) -> &::strided::Col<T> {
-        unsafe {
-            let ::raw::Mat { data, nrows, ncols } = self.repr();
+        self.try_col(c).unwrap()
+    }
+}
 
-            assert!(c < ncols);
+impl<T> Layout for ::Mat<T> {
+    fn aliases(&self, dst: &Region) -> bool {
+        let slice = self.as_ref();
+        let start = slice.as_ptr() as usize;
+        let end = start + slice.len() * mem::size_of::<T>();
 
-            mem::transmute(::strided::raw::Slice {
-                data: data.offset(c as isize),
-                len: nrows,
-                stride: ncols,
-            })
-        }
+        dst.is_aliased_by(start, end, (self.row_stride(), self.col_stride()))
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        mem::size_of::<T>()
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        self.ncols() as usize * mem::size_of::<T>()
     }
 }
 
@@ -176,6 +280,10 @@ impl<T> Matrix for ::Mat<T> {
 
 unsafe impl<T> Send for ::Mat<T> where T: Send {}
 
+impl<T> Shape for ::Mat<T> {
+    type Kind = ::kind::Mat;
+}
+
 unsafe impl<T> Sync for ::Mat<T> where T: Sync {}
 
 impl<T> UnsafeGet for ::Mat<T> where T: Clone {
@@ -183,7 +291,51 @@ impl<T> UnsafeGet for ::Mat<T> where T: Clone {
 
     #[inline(always)]
     unsafe fn unsafe_get(&self, (i, j): (u32, u32)) -> T {
-        let ::raw::Mat { data, ncols, .. } = self.repr();
-        (*data.offset((i * ncols + j) as isize)).clone()
+        let ::raw::Mat { data, nrows, ncols } = self.repr();
+
+        check::index("Mat", (nrows, ncols), (i, j));
+        let k = usize::from(i) * usize::from(ncols) + usize::from(j);
+
+        (*data.offset(check::offset("Mat", (i, j), k, self.nelems()))).clone()
+    }
+}
+
+impl<T> UnsafeGetLinear for ::Mat<T> where T: Clone {
+    #[inline(always)]
+    fn is_linear(&self, ncols: u32) -> bool {
+        self.ncols() == ncols
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> T {
+        check::linear("Mat", self.nelems(), i, 1);
+
+        (*self.repr().data.offset(i as isize)).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use error::Error;
+    use traits::Matrix;
+
+    #[test]
+    fn reshape() {
+        let mut buf = [0f32; 6];
+
+        assert_eq!(::Mat::try_reshape(&buf, (2, 3)).map(|m| m.size()), Ok((2, 3)));
+        assert_eq!(::Mat::try_reshape(&buf, (2, 2)).err(),
+                   Some(Error::BadReshape { len: 6, size: (2, 2) }));
+        assert_eq!(::Mat::try_reshape_mut(&mut buf, (4, 2)).err(),
+                   Some(Error::BadReshape { len: 6, size: (4, 2) }));
+    }
+
+    #[test]
+    fn col() {
+        let buf = [1f32, 2., 3., 4., 5., 6.];
+        let m = ::Mat::reshape(&buf, (2, 3));
+
+        assert_eq!(m.try_col(2).map(|c| c.size()), Ok((2, 1)));
+        assert_eq!(m.try_col(3).err(), Some(Error::OutOfRange { index: (0, 3), size: (2, 3) }));
     }
 }
@@ -0,0 +1,514 @@
+//! Built-in math functions with known costs
+//!
+//! Unlike `map`, whose closure is always assumed to cost a single flop, these functions carry an
+//! estimate of their real cost, so expensive expressions like `x.exp().ln()` cross the
+//! parallelism threshold when they should.
+//!
+//! To declare the cost of a custom function, implement `Function` for it and use `Math::apply`
+//! instead of `map`, or pass the cost along with a closure to `Math::map_with_cost`:
+//!
+//! ``` ignore
+//! // ~50 additions per element
+//! y[..] = x.map_with_cost(50, |x: f32| (0..10).fold(x, |x, _| x.sin() * x));
+//! ```
+
+use std::fmt;
+use std::ops::Sub;
+
+use error::{self, Result};
+use traits::{self, Describe, Flops, Layout, Matrix, Operand, Region, Shape, UnsafeGet};
+use traits::UnsafeGetLinear;
+
+/// A function that can be lazily applied to each element of an expression
+pub trait Function<T> {
+    /// The result of applying this function
+    type Output;
+
+    /// Returns the number of (floating point) operations required to evaluate this function once
+    fn cost(&self) -> usize;
+
+    /// Applies this function to `x`
+    fn call(&self, x: T) -> Self::Output;
+}
+
+/// Elements of a multiply-add, see `Math::mul_add`
+pub trait MulAdd {
+    /// Computes `self * b + c`
+    fn mul_add(self, b: Self, c: Self) -> Self;
+}
+
+/// Absolute value
+#[derive(Clone, Copy)]
+pub struct Abs;
+
+/// Clamps the element to the `[min, max]` range
+#[derive(Clone, Copy)]
+pub struct Clamp<T>(T, T);
+
+/// Cosine
+#[derive(Clone, Copy)]
+pub struct Cos;
+
+/// A closure with a declared cost, see `Math::map_with_cost`
+#[derive(Clone, Copy)]
+pub struct Costed<F>(usize, F);
+
+/// Exponential, `e^x`
+#[derive(Clone, Copy)]
+pub struct Exp;
+
+/// Largest integer less than or equal to the element
+#[derive(Clone, Copy)]
+pub struct Floor;
+
+/// Natural logarithm
+#[derive(Clone, Copy)]
+pub struct Ln;
+
+/// Raises the element to a floating point power
+#[derive(Clone, Copy)]
+pub struct Powf<T>(T);
+
+/// Raises the element to an integer power
+#[derive(Clone, Copy)]
+pub struct Powi(i32);
+
+/// Nearest integer, rounding half-way cases away from zero
+#[derive(Clone, Copy)]
+pub struct Round;
+
+/// Sine
+#[derive(Clone, Copy)]
+pub struct Sin;
+
+/// Square root
+#[derive(Clone, Copy)]
+pub struct Sqrt;
+
+/// Hyperbolic tangent
+#[derive(Clone, Copy)]
+pub struct Tanh;
+
+impl<F, T> Function<T> for Costed<F> where F: Fn<(T,)> {
+    type Output = F::Output;
+
+    #[inline(always)]
+    fn cost(&self) -> usize {
+        self.0
+    }
+
+    #[inline(always)]
+    fn call(&self, x: T) -> F::Output {
+        (self.1)(x)
+    }
+}
+
+// NB The float multiply-adds are fused, they are computed in software (much slower than `a * b +
+// c`) unless the crate is compiled for a processor with FMA instructions, e.g. with
+// `-C target-feature=+fma`
+macro_rules! mul_add {
+    (float: $($float:ident),+; int: $($int:ident),+) => {
+        $(
+            impl MulAdd for $float {
+                #[inline(always)]
+                fn mul_add(self, b: $float, c: $float) -> $float {
+                    $float::mul_add(self, b, c)
+                }
+            }
+         )+
+
+        $(
+            impl MulAdd for $int {
+                #[inline(always)]
+                fn mul_add(self, b: $int, c: $int) -> $int {
+                    self * b + c
+                }
+            }
+         )+
+    }
+}
+
+mul_add!(float: f32, f64; int: i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+// NB These costs are rough estimates, relative to the cost of a single addition
+macro_rules! float {
+    ($($ty:ident),+) => {
+        $(
+            impl Function<$ty> for Abs {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 1 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty { x.abs() }
+            }
+
+            impl Function<$ty> for Clamp<$ty> {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 2 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty {
+                    let Clamp(min, max) = *self;
+
+                    if x < min { min } else if x > max { max } else { x }
+                }
+            }
+
+            impl Function<$ty> for Cos {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 20 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty { x.cos() }
+            }
+
+            impl Function<$ty> for Exp {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 20 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty { x.exp() }
+            }
+
+            impl Function<$ty> for Floor {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 1 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty { x.floor() }
+            }
+
+            impl Function<$ty> for Ln {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 20 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty { x.ln() }
+            }
+
+            // NB `powf` is computed as `exp(y * ln(x))`
+            impl Function<$ty> for Powf<$ty> {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 40 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty { x.powf(self.0) }
+            }
+
+            // NB `powi` uses exponentiation by squaring, the exponent is only known at run time so
+            // assume a small one
+            impl Function<$ty> for Powi {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 4 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty { x.powi(self.0) }
+            }
+
+            impl Function<$ty> for Round {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 1 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty { x.round() }
+            }
+
+            impl Function<$ty> for Sin {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 20 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty { x.sin() }
+            }
+
+            impl Function<$ty> for Sqrt {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 10 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty { x.sqrt() }
+            }
+
+            impl Function<$ty> for Tanh {
+                type Output = $ty;
+
+                #[inline(always)]
+                fn cost(&self) -> usize { 25 }
+
+                #[inline(always)]
+                fn call(&self, x: $ty) -> $ty { x.tanh() }
+            }
+         )+
+    }
+}
+
+float!(f32, f64);
+
+/// Lazy math functions, available on every expression
+pub trait Math: UnsafeGet + Sized {
+    /// Lazily computes the absolute value of each element
+    fn abs(self) -> ::Apply<Abs, Self> where Abs: Function<Self::Output> {
+        ::Apply(Abs, self)
+    }
+
+    /// Lazily applies the function `f`, whose cost is known, to each element
+    fn apply<F>(self, f: F) -> ::Apply<F, Self> where F: Function<Self::Output> {
+        ::Apply(f, self)
+    }
+
+    /// Lazily clamps each element to the `[min, max]` range
+    fn clamp<T>(self, min: T, max: T) -> ::Apply<Clamp<T>, Self> where
+        Clamp<T>: Function<Self::Output>,
+    {
+        ::Apply(Clamp(min, max), self)
+    }
+
+    /// Lazily computes the cosine of each element
+    fn cos(self) -> ::Apply<Cos, Self> where Cos: Function<Self::Output> {
+        ::Apply(Cos, self)
+    }
+
+    /// Lazily computes the exponential of each element
+    fn exp(self) -> ::Apply<Exp, Self> where Exp: Function<Self::Output> {
+        ::Apply(Exp, self)
+    }
+
+    /// Lazily computes the floor of each element
+    fn floor(self) -> ::Apply<Floor, Self> where Floor: Function<Self::Output> {
+        ::Apply(Floor, self)
+    }
+
+    /// Lazily computes the natural logarithm of each element
+    fn ln(self) -> ::Apply<Ln, Self> where Ln: Function<Self::Output> {
+        ::Apply(Ln, self)
+    }
+
+    /// Lazily applies the closure `f` to each element, where `cost` is the cost of one call in
+    /// units of an `f32` addition
+    ///
+    /// Unlike `map`, whose closures are assumed to cost a single operation, this lets expensive
+    /// closures cross the parallelism threshold
+    fn map_with_cost<F>(self, cost: usize, f: F) -> ::Apply<Costed<F>, Self> where
+        F: Fn<(Self::Output,)>,
+    {
+        ::Apply(Costed(cost, f), self)
+    }
+
+    /// Lazily computes `self * b + c`, element-wise, in a single node
+    ///
+    /// Floating point elements are computed with a fused multiply-add, which rounds once (see
+    /// `MulAdd`)
+    fn mul_add<B, C>(self, b: B, c: C) -> ::Fma<Self, B, C> where
+        B: Operand + UnsafeGet<Output=Self::Output>,
+        C: Operand + UnsafeGet<Output=Self::Output>,
+        Self: Matrix,
+        Self::Output: MulAdd,
+    {
+        self.try_mul_add(b, c).unwrap()
+    }
+
+    /// Lazily raises each element to the floating point power `n`
+    fn powf<T>(self, n: T) -> ::Apply<Powf<T>, Self> where Powf<T>: Function<Self::Output> {
+        ::Apply(Powf(n), self)
+    }
+
+    /// Lazily raises each element to the integer power `n`
+    fn powi(self, n: i32) -> ::Apply<Powi, Self> where Powi: Function<Self::Output> {
+        ::Apply(Powi(n), self)
+    }
+
+    /// Lazily rounds each element to the nearest integer
+    fn round(self) -> ::Apply<Round, Self> where Round: Function<Self::Output> {
+        ::Apply(Round, self)
+    }
+
+    /// Lazily computes the sine of each element
+    fn sin(self) -> ::Apply<Sin, Self> where Sin: Function<Self::Output> {
+        ::Apply(Sin, self)
+    }
+
+    /// Lazily computes the square root of each element
+    fn sqrt(self) -> ::Apply<Sqrt, Self> where Sqrt: Function<Self::Output> {
+        ::Apply(Sqrt, self)
+    }
+
+    /// Lazily computes the hyperbolic tangent of each element
+    fn tanh(self) -> ::Apply<Tanh, Self> where Tanh: Function<Self::Output> {
+        ::Apply(Tanh, self)
+    }
+
+    /// Same as `mul_add`, but returns an error if the operands have different sizes
+    fn try_mul_add<B, C>(self, b: B, c: C) -> Result<::Fma<Self, B, C>> where
+        B: Operand + UnsafeGet<Output=Self::Output>,
+        C: Operand + UnsafeGet<Output=Self::Output>,
+        Self: Matrix,
+        Self::Output: MulAdd,
+    {
+        let size = self.size();
+        try!(error::check_operand(size, b.operand_size()));
+        try!(error::check_operand(size, c.operand_size()));
+
+        Ok(::Fma(self, b, c))
+    }
+
+    /// Same as `self - rhs`, but returns an error if the operands have different sizes
+    fn try_sub<B>(self, rhs: B) -> Result<::Sub<Self, B>> where
+        B: Operand + UnsafeGet,
+        Self: Matrix,
+        Self::Output: Sub<B::Output>,
+    {
+        try!(error::check_operand(self.size(), rhs.operand_size()));
+
+        Ok(::Sub(self, rhs))
+    }
+}
+
+impl<E> Math for E where E: UnsafeGet {}
+
+impl<F, M> Describe for ::Apply<F, M> where
+    F: Function<M::Output>,
+    M: Describe + UnsafeGet,
+{
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "Apply({}, ", traits::type_name::<F>()));
+        try!(self.1.fmt_tree(f));
+        f.write_str(")")
+    }
+}
+
+impl<F, M> Flops for ::Apply<F, M> where
+    F: Function<M::Output>,
+    M: Flops + UnsafeGet,
+{
+    #[inline(always)]
+    fn bytes() -> usize {
+        M::bytes()
+    }
+
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        self.1.flops() + self.0.cost()
+    }
+}
+
+impl<F, M> Layout for ::Apply<F, M> where
+    F: Function<M::Output>,
+    M: Layout + UnsafeGet,
+{
+    #[inline(always)]
+    fn aliases(&self, dst: &Region) -> bool {
+        self.1.aliases(dst)
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        self.1.col_stride()
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        self.1.row_stride()
+    }
+}
+
+impl<F, M> Matrix for ::Apply<F, M> where
+    F: Function<M::Output>,
+    M: Matrix + UnsafeGet,
+{
+    #[inline(always)]
+    fn nrows(&self) -> u32 {
+        M::nrows(&self.1)
+    }
+
+    #[inline(always)]
+    fn ncols(&self) -> u32 {
+        M::ncols(&self.1)
+    }
+
+    #[inline(always)]
+    fn nelems(&self) -> usize {
+        M::nelems(&self.1)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> (u32, u32) {
+        M::size(&self.1)
+    }
+}
+
+impl<F, M> Shape for ::Apply<F, M> where
+    F: Function<M::Output>,
+    M: Shape + UnsafeGet,
+{
+    type Kind = M::Kind;
+}
+
+impl<F, M> UnsafeGet for ::Apply<F, M> where
+    F: Function<M::Output>,
+    M: UnsafeGet,
+{
+    type Output = F::Output;
+
+    #[inline(always)]
+    unsafe fn unsafe_get(&self, i: (u32, u32)) -> F::Output {
+        self.0.call(self.1.unsafe_get(i))
+    }
+}
+
+impl<F, M> UnsafeGetLinear for ::Apply<F, M> where
+    F: Function<M::Output>,
+    M: UnsafeGetLinear,
+{
+    #[inline(always)]
+    fn is_linear(&self, ncols: u32) -> bool {
+        self.1.is_linear(ncols)
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> F::Output {
+        self.0.call(self.1.unsafe_get_linear(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use error::Error;
+
+    use super::Math;
+
+    #[test]
+    fn mismatch() {
+        let (x, y) = ([1f32, 2., 3.], [1f32, 2.]);
+        let (x, y) = (::Col::from_slice(&x), ::Col::from_slice(&y));
+
+        assert!(x.try_sub(1f32).is_ok());
+        assert_eq!(x.try_sub(y).err(), Some(Error::ShapeMismatch { lhs: (3, 1), rhs: (2, 1) }));
+        assert!(x.try_mul_add(2f32, x).is_ok());
+        assert_eq!(x.try_mul_add(y, 1f32).err(),
+                   Some(Error::ShapeMismatch { lhs: (3, 1), rhs: (2, 1) }));
+        assert_eq!(x.try_mul_add(2f32, y).err(),
+                   Some(Error::ShapeMismatch { lhs: (3, 1), rhs: (2, 1) }));
+    }
+}
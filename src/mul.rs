@@ -1,15 +1,58 @@
+use std::{cmp, fmt};
 use std::ops::Mul;
 
-use traits::{Flops, Matrix, UnsafeGet};
+use cost::Arith;
+use kind::Broadcast;
+use traits::{Describe, Flops, Layout, Matrix, Region, Shape, UnsafeGet, UnsafeGetLinear};
+
+impl<A, B> Describe for ::Mul<A, B> where
+    A: Describe + UnsafeGet,
+    B: Describe + UnsafeGet,
+    A::Output: Mul<B::Output>,
+{
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(f.write_str("Mul("));
+        try!(self.0.fmt_tree(f));
+        try!(f.write_str(", "));
+        try!(self.1.fmt_tree(f));
+        f.write_str(")")
+    }
+}
 
 impl<A, B> Flops for ::Mul<A, B> where
     A: Flops + UnsafeGet,
     B: Flops + UnsafeGet,
+    A::Output: Mul<B::Output> + Arith,
+{
+    #[inline(always)]
+    fn bytes() -> usize {
+        A::bytes() + B::bytes()
+    }
+
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        self.0.flops() + self.1.flops() + <A::Output as Arith>::mul()
+    }
+}
+
+impl<A, B> Layout for ::Mul<A, B> where
+    A: Layout + UnsafeGet,
+    B: Layout + UnsafeGet,
     A::Output: Mul<B::Output>,
 {
     #[inline(always)]
-    fn flops() -> usize {
-        A::flops() + B::flops() + 1
+    fn aliases(&self, dst: &Region) -> bool {
+        self.0.aliases(dst) || self.1.aliases(dst)
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        cmp::max(self.0.col_stride(), self.1.col_stride())
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        cmp::max(self.0.row_stride(), self.1.row_stride())
     }
 }
 
@@ -39,6 +82,15 @@ impl<A, B, C> Matrix for ::Mul<A, B> where
     }
 }
 
+impl<A, B> Shape for ::Mul<A, B> where
+    A: Shape + UnsafeGet,
+    B: Shape + UnsafeGet,
+    A::Output: Mul<B::Output>,
+    A::Kind: Broadcast<B::Kind>,
+{
+    type Kind = <A::Kind as Broadcast<B::Kind>>::Output;
+}
+
 impl<A, B, C> UnsafeGet for ::Mul<A, B> where
     A: UnsafeGet,
     B: UnsafeGet,
@@ -51,3 +103,19 @@ impl<A, B, C> UnsafeGet for ::Mul<A, B> where
         self.0.unsafe_get(i) * self.1.unsafe_get(i)
     }
 }
+
+impl<A, B, C> UnsafeGetLinear for ::Mul<A, B> where
+    A: UnsafeGetLinear,
+    B: UnsafeGetLinear,
+    A::Output: Mul<B::Output, Output=C>,
+{
+    #[inline(always)]
+    fn is_linear(&self, ncols: u32) -> bool {
+        self.0.is_linear(ncols) && self.1.is_linear(ncols)
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> C {
+        self.0.unsafe_get_linear(i) * self.1.unsafe_get_linear(i)
+    }
+}
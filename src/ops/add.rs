@@ -1,7 +1,47 @@
 use std::ops::{Add, Mul};
 
+use error::{self, Result};
 use traits::{Matrix, UnsafeGet};
 
+impl<A, B, C> ::Mul<A, B> where
+    A: UnsafeGet,
+    B: Matrix + UnsafeGet,
+    A::Output: Mul<B::Output, Output=C>,
+{
+    /// Same as `self + rhs`, but returns an error if the operands have different sizes
+    pub fn try_add<D, E, F>(self, rhs: ::Mul<D, E>)
+        -> Result<::Add<::Mul<A, B>, ::Mul<D, E>>> where
+        D: UnsafeGet,
+        E: Matrix + UnsafeGet,
+        D::Output: Mul<E::Output, Output=F>,
+        C: Add<F>,
+    {
+        try!(error::check_size(self.size(), rhs.size()));
+
+        Ok(::Add(self, rhs))
+    }
+}
+
+impl<A, B> ::Add<A, B> where
+    A: UnsafeGet,
+    B: Matrix + UnsafeGet,
+    A::Output: Add<B::Output>,
+{
+    /// Same as `self + rhs`, but returns an error if the operands have different sizes
+    pub fn try_add<C, D, E, F>(self, rhs: ::Mul<C, D>)
+        -> Result<::Add<A, ::Add<B, ::Mul<C, D>>>> where
+        A::Output: Add<F>,
+        C: UnsafeGet,
+        D: Matrix + UnsafeGet,
+        C::Output: Mul<D::Output, Output=E>,
+        B::Output: Add<E, Output=F>,
+    {
+        try!(error::check_size(self.size(), rhs.size()));
+
+        Ok(::Add(self.0, ::Add(self.1, rhs)))
+    }
+}
+
 // `a * b + c * d`
 impl<A, B, C, D, E, F> Add<::Mul<D, E>> for ::Mul<A, B> where
     A: UnsafeGet,
@@ -15,9 +55,7 @@ impl<A, B, C, D, E, F> Add<::Mul<D, E>> for ::Mul<A, B> where
     type Output = ::Add<::Mul<A, B>, ::Mul<D, E>>;
 
     fn add(self, rhs: ::Mul<D, E>) -> Self::Output {
-        assert_eq!(self.size(), rhs.size());
-
-        ::Add(self, rhs)
+        self.try_add(rhs).unwrap()
     }
 }
 
@@ -34,8 +72,6 @@ impl<A, B, C, D, E, F> Add<::Mul<C, D>> for ::Add<A, B> where
     type Output = ::Add<A, ::Add<B, ::Mul<C, D>>>;
 
     fn add(self, rhs: ::Mul<C, D>) -> Self::Output {
-        assert_eq!(self.size(), rhs.size());
-
-        ::Add(self.0, ::Add(self.1, rhs))
+        self.try_add(rhs).unwrap()
     }
 }
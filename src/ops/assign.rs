@@ -1,98 +1,341 @@
-use std::cmp;
 use std::ops::{IndexAssign, RangeFull};
-use std::thread;
+use std::sync::Mutex;
+use std::{cmp, mem};
 
 use cast::From;
-use num_cpus;
 
-use traits::{Flops, Matrix, UnsafeGet};
+use check;
+use cost;
+use error::{self, Result};
+use policy::{self, Policy, Schedule};
+use pool::{self, Job};
+use stats::{self, Recorder};
+use traits::{Expr, Matrix, Region, UnsafeGet, UnsafeGetLinear};
 
-/// If FLOPS > THRESHOLD, then use fork-join parallelism
-const THRESHOLD: usize = 1_000_000;
+/// Size of a cache line, in bytes
+const CACHE_LINE: usize = 64;
+
+/// Size of the L1 data cache, in bytes, used to pick the side of the tiles
+const L1: usize = 32 * 1024;
+
+impl<T> ::Mat<T> {
+    /// Evaluates `rhs` and stores the result in this matrix, using the parallelism `policy`
+    /// instead of the global one
+    pub fn assign_with<R>(&mut self, policy: Policy, rhs: R) where
+        R: Expr<Output=T, Kind=::kind::Mat> + Sync,
+        T: Send,
+    {
+        self.try_assign_with(policy, rhs).unwrap()
+    }
+
+    /// Same as `self[..] = rhs`, but returns an error if `rhs` doesn't have the same size as this
+    /// matrix
+    pub fn try_assign<R>(&mut self, rhs: R) -> Result<()> where
+        R: Expr<Output=T, Kind=::kind::Mat> + Sync,
+        T: Send,
+    {
+        self.try_assign_with(policy::get(), rhs)
+    }
+
+    /// Same as `assign_with`, but returns an error if `rhs` doesn't have the same size as this
+    /// matrix
+    pub fn try_assign_with<R>(&mut self, policy: Policy, rhs: R) -> Result<()> where
+        R: Expr<Output=T, Kind=::kind::Mat> + Sync,
+        T: Send,
+    {
+        try!(error::check_size(self.size(), rhs.size()));
+
+        unsafe {
+            let ncols = self.ncols();
+            eval(self.as_mut(), ncols, &rhs, policy)
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> ::Col<T> {
+    /// Evaluates `rhs` and stores the result in this vector, using the parallelism `policy`
+    /// instead of the global one
+    pub fn assign_with<R>(&mut self, policy: Policy, rhs: R) where
+        R: Expr<Output=T, Kind=::kind::Col> + Sync,
+        T: Send,
+    {
+        self.try_assign_with(policy, rhs).unwrap()
+    }
+
+    /// Same as `self[..] = rhs`, but returns an error if `rhs` doesn't have the same size as this
+    /// vector
+    pub fn try_assign<R>(&mut self, rhs: R) -> Result<()> where
+        R: Expr<Output=T, Kind=::kind::Col> + Sync,
+        T: Send,
+    {
+        self.try_assign_with(policy::get(), rhs)
+    }
+
+    /// Same as `assign_with`, but returns an error if `rhs` doesn't have the same size as this
+    /// vector
+    pub fn try_assign_with<R>(&mut self, policy: Policy, rhs: R) -> Result<()> where
+        R: Expr<Output=T, Kind=::kind::Col> + Sync,
+        T: Send,
+    {
+        try!(error::check_size(self.size(), rhs.size()));
+
+        unsafe {
+            eval(self.as_mut(), 1, &rhs, policy)
+        }
+
+        Ok(())
+    }
+}
 
-// FIXME `Mat[..] = &Col + &Col` should be rejected at compile time not at run time
 impl<T, R> IndexAssign<RangeFull, R> for ::Mat<T> where
-    R: Flops + Matrix + Sync + UnsafeGet<Output=T>,
+    R: Expr<Output=T, Kind=::kind::Mat> + Sync,
     T: Send,
 {
     fn index_assign(&mut self, _: RangeFull, rhs: R) {
-        unsafe {
-            assert_eq!(self.size(), rhs.size());
+        self.assign_with(policy::get(), rhs)
+    }
+}
 
-            let nelems = self.nelems();
-            let nflops = R::flops() * nelems;
+impl<T, R> IndexAssign<RangeFull, R> for ::Col<T> where
+    R: Expr<Output=T, Kind=::kind::Col> + Sync,
+    T: Send,
+{
+    fn index_assign(&mut self, _: RangeFull, rhs: R) {
+        self.assign_with(policy::get(), rhs)
+    }
+}
 
-            debug!("Estimated flops: {}", nflops);
+/// Evaluates `rhs` into `dst`, a row major buffer with `ncols` columns
+///
+/// Unsafe because `rhs` must have the same size as `dst`
+unsafe fn eval<T, R>(dst: &mut [T], ncols: u32, rhs: &R, policy: Policy) where
+    R: Expr<Output=T> + Sync,
+    T: Send,
+{
+    let nelems = dst.len();
+    let cost = cost::of::<T, R>(rhs, nelems);
+
+    debug!("Expression: {}", rhs.describe());
+    debug!("Estimated cost: {}", cost);
 
-            let ncols = usize::from(self.ncols());
-            let ncpus = num_cpus::get();
-            if nflops > THRESHOLD && ncpus > 1 {
-                let nrows = self.nrows();
-                let nthreads = u32::from(cmp::min(ncpus, nflops / THRESHOLD)).unwrap();
+    if nelems == 0 {
+        return
+    }
 
-                debug!("Spinning up {} threads", nthreads);
+    let nrows = u32::from(nelems / usize::from(ncols)).unwrap();
+    let bytes = (R::bytes() + mem::size_of::<T>()).saturating_mul(nelems);
+    let recorder = Recorder::start();
+    let expr = check::describe(rhs);
+    let expr = &expr;
 
-                // rows per horizontal stripe
-                let sz = (nrows - 1) / nthreads + 1;
+    // NB If `rhs` reads elements of `dst` other than the one being written, the result would depend
+    // on the evaluation order, so `rhs` is evaluated into a temporary buffer first
+    if rhs.aliases(&Region::of(dst, ncols)) {
+        warn!("The destination aliases one of the operands, evaluating into a temporary");
 
-                let rhs = &rhs;
-                self.as_mut().chunks_mut(ncols * usize::from(sz)).zip(0..).map(move |(hstripe, i)| {
-                    thread::scoped(move || {
-                        for (row, i) in hstripe.chunks_mut(ncols).zip(i*sz..) {
-                            for (dst, j) in row.iter_mut().zip(0..) {
-                                *dst = rhs.unsafe_get((i, j))
-                            }
-                        }
-                    })
-                }).collect::<Vec<_>>();
-            } else {
-                for (row, i) in self.as_mut().chunks_mut(ncols).zip(0..) {
-                    for (dst, j) in row.iter_mut().zip(0..) {
-                        *dst = rhs.unsafe_get((i, j))
-                    }
+        stats::chunk(recorder.as_ref(), 0, nelems, || check::within(expr, || {
+            let mut tmp = Vec::with_capacity(nelems);
+            for i in 0..nrows {
+                for j in 0..ncols {
+                    tmp.push(rhs.unsafe_get((i, j)))
                 }
             }
+
+            for (dst, x) in dst.iter_mut().zip(tmp.into_iter()) {
+                *dst = x
+            }
+        }));
+
+        return stats::finish(recorder, nelems, bytes, cost, 1)
+    }
+
+    // NB If all the leaves are laid out like the destination, the destination is filled using a
+    // flat loop. Otherwise, walking along the rows of the destination, a leaf whose consecutive
+    // elements don't share a cache line (e.g. a transposed matrix) would load a new cache line per
+    // element, so the destination is traversed in square tiles instead
+    let fill: unsafe fn(&mut [T], u32, u32, &R) = if rhs.is_linear(ncols) {
+        debug!("Using linear evaluation");
+
+        linear
+    } else if ncols > 1 && rhs.col_stride() >= CACHE_LINE {
+        debug!("Using tiled evaluation, column stride: {} bytes", rhs.col_stride());
+
+        tiled
+    } else {
+        stripe
+    };
+
+    let nthreads = policy.nthreads(cost);
+
+    {
+        let recorder = recorder.as_ref();
+
+        // Fills `chunk` with the rows of `rhs` that start at row `i`
+        let run = |chunk: &mut [T], i: u32| {
+            let start = usize::from(i) * usize::from(ncols);
+
+            stats::chunk(recorder, start, chunk.len(), || {
+                check::within(expr, || fill(chunk, ncols, i, rhs))
+            })
+        };
+        let run = &run;
+
+        if nthreads == 1 {
+            run(dst, 0)
+        } else {
+            debug!("Spinning up {} threads", nthreads);
+
+            // elements per row
+            let row = usize::from(ncols);
+
+            match policy.get_schedule() {
+                Schedule::Static => {
+                    let nthreads = u32::from(nthreads).unwrap();
+
+                    // rows per horizontal stripe
+                    let sz = (nrows - 1) / nthreads + 1;
+
+                    let stripes = dst.chunks_mut(row * usize::from(sz)).zip(0..);
+                    pool::get().execute(stripes.map(move |(hstripe, i)| {
+                        Box::new(move || {
+                            run(hstripe, i * sz)
+                        }) as Job
+                    }).collect());
+                },
+                Schedule::Dynamic => {
+                    // rows per chunk
+                    let bytes = cmp::max(1, mem::size_of::<T>()) * row;
+                    let sz = u32::from(cmp::max(1, policy.get_chunk_size() / bytes))
+                        .unwrap_or(nrows);
+
+                    debug!("Distributing {} chunks of {} rows", (nrows - 1) / sz + 1, sz);
+
+                    let chunks = Mutex::new(dst.chunks_mut(row * usize::from(sz)).zip(0..));
+                    let chunks = &chunks;
+                    pool::get().execute((0..nthreads).map(move |_| {
+                        Box::new(move || {
+                            loop {
+                                let next = chunks.lock().unwrap().next();
+
+                                match next {
+                                    None => break,
+                                    Some((chunk, i)) => run(chunk, i * sz),
+                                }
+                            }
+                        }) as Job
+                    }).collect());
+                },
+            }
         }
     }
+
+    stats::finish(recorder, nelems, bytes, cost, nthreads)
 }
 
-impl<T, R> IndexAssign<RangeFull, R> for ::Col<T> where
-    R: Flops + Matrix + Sync + UnsafeGet<Output=T>,
-    T: Send,
+/// Evaluates the rows of `rhs` that start at row `i` into `stripe`
+unsafe fn stripe<T, R>(stripe: &mut [T], ncols: u32, i: u32, rhs: &R) where
+    R: UnsafeGet<Output=T>,
 {
-    fn index_assign(&mut self, _: RangeFull, rhs: R) {
-        unsafe {
-            assert_eq!(self.size(), rhs.size());
-
-            let nelems = self.nelems();
-            let nflops = R::flops() * nelems;
-
-            debug!("Estimated flops: {}", nflops);
-
-            let ncpus = num_cpus::get();
-            if nflops > THRESHOLD && ncpus > 1 {
-                let nrows = self.nrows();
-                let nthreads = u32::from(cmp::min(ncpus, nflops / THRESHOLD)).unwrap();
-
-                debug!("Spinning up {} threads", nthreads);
-
-                // rows per horizontal stripe
-                let sz = (nrows - 1) / nthreads + 1;
-
-                let rhs = &rhs;
-                self.as_mut().chunks_mut(usize::from(sz)).zip(0..).map(move |(chunk, i)| {
-                    thread::scoped(move || {
-                        let offset = i * sz;
-                        for (dst, i) in chunk.iter_mut().zip(0..) {
-                            *dst = rhs.unsafe_get((offset + i, 0))
-                        }
-                    })
-                }).collect::<Vec<_>>();
-            } else {
-                for (dst, i) in self.iter_mut().zip(0..) {
-                    *dst = rhs.unsafe_get((i, 0))
+    for (row, i) in stripe.chunks_mut(usize::from(ncols)).zip(i..) {
+        for (dst, j) in row.iter_mut().zip(0..) {
+            *dst = rhs.unsafe_get((i, j))
+        }
+    }
+}
+
+/// Same as `stripe`, but uses linear indexing
+unsafe fn linear<T, R>(stripe: &mut [T], ncols: u32, i: u32, rhs: &R) where
+    R: UnsafeGetLinear<Output=T>,
+{
+    let offset = usize::from(i) * usize::from(ncols);
+
+    for (dst, k) in stripe.iter_mut().zip(offset..) {
+        *dst = rhs.unsafe_get_linear(k)
+    }
+}
+
+/// Same as `stripe`, but traverses `stripe` in square tiles
+unsafe fn tiled<T, R>(stripe: &mut [T], ncols: u32, i: u32, rhs: &R) where
+    R: UnsafeGet<Output=T>,
+{
+    // the tile of the destination and the tiles of the leaves should fit in the L1 cache
+    let side = ((L1 / 2 / cmp::max(1, mem::size_of::<T>())) as f64).sqrt() as u32;
+    let nrows = u32::from(stripe.len() / usize::from(ncols)).unwrap();
+
+    let mut r0 = 0;
+    while r0 < nrows {
+        let r1 = cmp::min(r0 + side, nrows);
+
+        let mut c0 = 0;
+        while c0 < ncols {
+            let c1 = cmp::min(c0 + side, ncols);
+
+            for r in r0..r1 {
+                let row = usize::from(r) * usize::from(ncols);
+
+                for c in c0..c1 {
+                    *stripe.get_unchecked_mut(row + usize::from(c)) = rhs.unsafe_get((i + r, c))
                 }
             }
+
+            c0 = c1;
         }
+
+        r0 = r1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use error::Error;
+
+    // NB Safe code can't read the destination of an assignment, the aliases are built with raw
+    // pointers like unsafe callers would
+
+    #[test]
+    fn aliased_transpose() {
+        let mut buf = [1f32, 2., 3., 4.];
+
+        {
+            let m = ::Mat::reshape_mut(&mut buf, (2, 2));
+            let view = unsafe { &*(m as *const ::Mat<f32>) };
+
+            // evaluated in place, the element `(1, 0)` would read the already written `(0, 1)`
+            m[..] = view.t();
+        }
+
+        assert_eq!(buf, [1., 3., 2., 4.]);
+    }
+
+    #[test]
+    fn same_layout() {
+        let mut buf = [1f32, 2., 3., 4.];
+
+        {
+            let m = ::Mat::reshape_mut(&mut buf, (2, 2));
+            let view = unsafe { &*(m as *const ::Mat<f32>) };
+
+            // each element is read right before being overwritten, no temporary is needed
+            m[..] = ::Mul(2f32, view);
+        }
+
+        assert_eq!(buf, [2., 4., 6., 8.]);
+    }
+
+    #[test]
+    fn shape_mismatch() {
+        let mut buf = [0f32; 2];
+
+        {
+            let y = ::Col::from_slice_mut(&mut buf);
+
+            assert_eq!(y.try_assign(::Col::from_slice(&[1f32, 2., 3.])),
+                       Err(Error::ShapeMismatch { lhs: (2, 1), rhs: (3, 1) }));
+        }
+
+        // the destination is left untouched
+        assert_eq!(buf, [0., 0.]);
     }
 }
@@ -1,3 +1,4 @@
 mod add;
 mod assign;
 mod mul;
+mod sub;
@@ -1,5 +1,6 @@
 use std::ops::Mul;
 
+use math::Function;
 use traits::UnsafeGet;
 
 // `x.sin() * 2.`
@@ -14,3 +15,16 @@ impl<F, A, B> Mul<B> for ::Map<F, A> where
         ::Mul(rhs, self)
     }
 }
+
+// `x.cast::<f32>() * 2.`
+impl<F, A, B> Mul<B> for ::Apply<F, A> where
+    A: UnsafeGet,
+    F: Function<A::Output>,
+    B: Mul<F::Output> + UnsafeGet<Output=B>,
+{
+    type Output = ::Mul<B, ::Apply<F, A>>;
+
+    fn mul(self, rhs: B) -> ::Mul<B, ::Apply<F, A>> {
+        ::Mul(rhs, self)
+    }
+}
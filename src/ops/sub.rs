@@ -0,0 +1,59 @@
+use std::ops::{Add, Mul, Sub};
+
+use kernel::Kernel;
+use leaf::Leaf;
+use math::{Function, Math, MulAdd};
+use traits::{Matrix, Operand, UnsafeGet};
+
+// `lhs - rhs`, where `lhs` is a leaf or a proxy, and `rhs` is a leaf, a proxy or a scalar
+macro_rules! sub {
+    ($($lhs:ty: [$($g:tt)*] where [$($w:tt)*];)+) => {
+        $(
+            impl<$($g)* R> Sub<R> for $lhs where
+                $($w)*
+                $lhs: Matrix + UnsafeGet,
+                <$lhs as UnsafeGet>::Output: Sub<R::Output>,
+                R: Operand + UnsafeGet,
+            {
+                type Output = ::Sub<$lhs, R>;
+
+                fn sub(self, rhs: R) -> ::Sub<$lhs, R> {
+                    self.try_sub(rhs).unwrap()
+                }
+            }
+         )+
+    }
+}
+
+sub! {
+    // `&x - y`
+    &'a ::Col<T>: ['a, T,] where [];
+    &'a ::Mat<T>: ['a, T,] where [];
+    &'a ::strided::Col<T>: ['a, T,] where [];
+
+    // `&x.shift(0, 1) - x`
+    &'a ::Shift<M>: ['a, M,] where [M: UnsafeGet,];
+
+    // `(x + y) - z`
+    ::Add<A, B>: [A, B,] where [A: UnsafeGet, B: UnsafeGet, A::Output: Add<B::Output>,];
+    ::Apply<F, M>: [F, M,] where [F: Function<M::Output>, M: UnsafeGet,];
+    ::Convolve<M, K>: [M, K,] where [K: Kernel<Elem=M::Output>, M: UnsafeGet,];
+    ::Custom<L>: [L,] where [L: Leaf,];
+    ::Fma<A, B, C>: [A, B, C,] where [
+        A: UnsafeGet,
+        B: UnsafeGet<Output=A::Output>,
+        C: UnsafeGet<Output=A::Output>,
+        A::Output: MulAdd,
+    ];
+    ::Map<F, M>: [F, M,] where [F: Fn<(M::Output,)>, M: UnsafeGet,];
+    ::Mul<A, B>: [A, B,] where [A: UnsafeGet, B: UnsafeGet, A::Output: Mul<B::Output>,];
+    ::Select<M, A, B>: [M, A, B,] where [
+        M: UnsafeGet<Output=bool>,
+        A: UnsafeGet,
+        B: UnsafeGet<Output=A::Output>,
+    ];
+    ::Separable<T, H, K>: [T, H, K,] where [K: Kernel<Elem=T>,];
+    ::Shift<M>: [M,] where [M: UnsafeGet,];
+    ::Sub<A, B>: [A, B,] where [A: UnsafeGet, B: UnsafeGet, A::Output: Sub<B::Output>,];
+    ::Transpose<M>: [M,] where [M: UnsafeGet,];
+}
@@ -0,0 +1,203 @@
+//! Parallelism policy of the evaluator
+//!
+//! The global policy is initialized, on first use, from the following environment variables:
+//!
+//! - `ET_NUM_THREADS`: maximum number of threads used to evaluate an assignment. `1` forces serial
+//!   evaluation. Defaults to the number of CPUs.
+//! - `ET_THRESHOLD`: an assignment is only parallelized if its estimated cost (see the `cost`
+//!   module) exceeds this threshold. Defaults to 1,000,000.
+//! - `ET_SCHEDULE`: how the work is distributed among the threads, either `static` or `dynamic`.
+//!   Defaults to `static`.
+//! - `ET_CALIBRATION`: path to a file written by `calibrate::Calibration::save`, its threshold is
+//!   used unless `ET_THRESHOLD` is also set.
+//!
+//! The global policy can be changed at runtime with `policy::set`, and a policy can also be
+//! specified per assignment with `assign_with`.
+
+use std::sync::{Once, ONCE_INIT, RwLock};
+use std::{env, mem};
+
+use num_cpus;
+
+use calibrate::Calibration;
+
+/// Default threshold, if COST > THRESHOLD, then use fork-join parallelism
+const THRESHOLD: usize = 1_000_000;
+
+static INIT: Once = ONCE_INIT;
+// NB The policy is stored behind a single lock, so a concurrent `set` is never observed half
+// applied
+static mut GLOBAL: *const RwLock<Policy> = 0 as *const RwLock<Policy>;
+
+/// Default chunk size, in bytes, used by the dynamic schedule
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// How the output of an assignment is distributed among the threads
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Schedule {
+    /// The output is split in as many equally sized stripes as threads
+    Static,
+    /// The output is split in many small chunks that are handed out to the threads as they
+    /// become idle. Use this when the cost per element varies, e.g. `map` closures with data
+    /// dependent branches.
+    Dynamic,
+}
+
+/// Decides how many threads are used to evaluate an assignment, and how the work is distributed
+/// among them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Policy {
+    chunk_size: usize,
+    num_threads: usize,
+    schedule: Schedule,
+    threshold: usize,
+}
+
+impl Policy {
+    /// Returns the default policy: use up to one thread per CPU, if the estimated cost of the
+    /// assignment exceeds 1,000,000, and split the work statically
+    pub fn new() -> Policy {
+        Policy {
+            chunk_size: CHUNK_SIZE,
+            num_threads: num_cpus::get(),
+            schedule: Schedule::Static,
+            threshold: THRESHOLD,
+        }
+    }
+
+    /// Sets the size, in bytes, of the chunks of the output handed out by the dynamic schedule.
+    /// Defaults to 16 KiB.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is zero
+    pub fn chunk_size(self, bytes: usize) -> Policy {
+        assert!(bytes > 0);
+
+        Policy { chunk_size: bytes, ..self }
+    }
+
+    /// Returns a policy that always evaluates in the calling thread
+    pub fn serial() -> Policy {
+        Policy::new().num_threads(1)
+    }
+
+    /// Caps the number of threads to `n`
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero
+    pub fn num_threads(self, n: usize) -> Policy {
+        assert!(n > 0);
+
+        Policy { num_threads: n, ..self }
+    }
+
+    /// Changes how the work is distributed among the threads
+    pub fn schedule(self, schedule: Schedule) -> Policy {
+        Policy { schedule: schedule, ..self }
+    }
+
+    /// Only parallelize assignments whose estimated cost exceeds `cost`, in units of an `f32`
+    /// addition
+    ///
+    /// # Panics
+    ///
+    /// If `cost` is zero
+    pub fn threshold(self, cost: usize) -> Policy {
+        assert!(cost > 0);
+
+        Policy { threshold: cost, ..self }
+    }
+
+    /// Returns the chunk size, in bytes, used by the dynamic schedule
+    pub fn get_chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Returns the maximum number of threads
+    pub fn get_num_threads(&self) -> usize {
+        self.num_threads
+    }
+
+    /// Returns how the work is distributed among the threads
+    pub fn get_schedule(&self) -> Schedule {
+        self.schedule
+    }
+
+    /// Returns the parallelism threshold
+    pub fn get_threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns the number of threads that should be used to evaluate an assignment with an
+    /// estimated `cost`
+    pub fn nthreads(&self, cost: usize) -> usize {
+        if cost > self.threshold {
+            let n = cost / self.threshold;
+
+            if n < self.num_threads { n } else { self.num_threads }
+        } else {
+            1
+        }
+    }
+}
+
+/// Returns the global policy, initializing it if necessary
+fn global() -> &'static RwLock<Policy> {
+    unsafe {
+        INIT.call_once(|| {
+            GLOBAL = mem::transmute(Box::new(RwLock::new(init())));
+        });
+
+        &*GLOBAL
+    }
+}
+
+/// Returns the initial policy, read from the environment
+fn init() -> Policy {
+    let mut policy = Policy::new();
+
+    if let Ok(n) = env::var("ET_NUM_THREADS") {
+        match n.parse() {
+            Ok(n) if n > 0 => policy = policy.num_threads(n),
+            _ => warn!("ignoring invalid ET_NUM_THREADS: {:?}", n),
+        }
+    }
+
+    if let Some(path) = env::var_os("ET_CALIBRATION") {
+        match Calibration::load(&path) {
+            Ok(calibration) => policy = policy.threshold(calibration.threshold),
+            Err(e) => warn!("ignoring ET_CALIBRATION ({:?}): {}", path, e),
+        }
+    }
+
+    if let Ok(cost) = env::var("ET_THRESHOLD") {
+        match cost.parse() {
+            Ok(cost) if cost > 0 => policy = policy.threshold(cost),
+            _ => warn!("ignoring invalid ET_THRESHOLD: {:?}", cost),
+        }
+    }
+
+    if let Ok(schedule) = env::var("ET_SCHEDULE") {
+        match &*schedule {
+            "static" => policy = policy.schedule(Schedule::Static),
+            "dynamic" => policy = policy.schedule(Schedule::Dynamic),
+            _ => warn!("ignoring invalid ET_SCHEDULE: {:?}", schedule),
+        }
+    }
+
+    debug!("Initial policy: {:?}", policy);
+
+    policy
+}
+
+/// Returns the global policy
+pub fn get() -> Policy {
+    *global().read().unwrap()
+}
+
+/// Changes the global policy, this affects all the subsequent `y[..] = expr` assignments
+pub fn set(policy: Policy) {
+    *global().write().unwrap() = policy
+}
@@ -0,0 +1,183 @@
+//! A persistent pool of worker threads
+//!
+//! Spawning and joining OS threads on every assignment dominates the evaluation time of mid-sized
+//! expressions, so the workers are spawned once (lazily, one per CPU) and reused by all the
+//! evaluators.
+
+use std::cell::Cell;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, Once, ONCE_INIT};
+use std::{mem, thread};
+
+use num_cpus;
+
+/// A job that borrows data from the stack of the thread that submitted it
+pub type Job<'a> = Box<FnMut() + Send + 'a>;
+
+// `true` in the worker threads of the pool
+thread_local!(static WORKER: Cell<bool> = Cell::new(false));
+
+/// A fixed set of worker threads that execute jobs in fork-join fashion
+pub struct Pool {
+    nthreads: usize,
+    queue: Mutex<Sender<Job<'static>>>,
+}
+
+/// Signals the completion of a job, even if the job panicked
+struct Done(Sender<bool>);
+
+impl Drop for Done {
+    fn drop(&mut self) {
+        self.0.send(thread::panicking()).ok();
+    }
+}
+
+impl Pool {
+    fn new(nthreads: usize) -> Pool {
+        let (tx, rx) = mpsc::channel::<Job<'static>>();
+        let rx = Arc::new(Mutex::new(rx));
+
+        for _ in 0..nthreads {
+            let rx = rx.clone();
+
+            thread::spawn(move || worker(rx));
+        }
+
+        debug!("Spawned a pool of {} worker threads", nthreads);
+
+        Pool {
+            nthreads: nthreads,
+            queue: Mutex::new(tx),
+        }
+    }
+
+    /// Returns the number of worker threads in this pool
+    pub fn nthreads(&self) -> usize {
+        self.nthreads
+    }
+
+    /// Executes all the `jobs` in parallel, and blocks until all of them have finished
+    ///
+    /// The last job is executed in the calling thread, the rest are dispatched to the workers.
+    ///
+    /// If the calling thread is one of the workers (i.e. a job performs a parallel assignment), all
+    /// the jobs are executed in the calling thread: otherwise every worker could end up blocked
+    /// waiting for jobs that are stuck in the queue.
+    ///
+    /// # Panics
+    ///
+    /// If any of the jobs panicked
+    pub fn execute<'a>(&self, mut jobs: Vec<Job<'a>>) {
+        if WORKER.with(|worker| worker.get()) {
+            for mut job in jobs {
+                job()
+            }
+
+            return
+        }
+
+        let mut last = match jobs.pop() {
+            None => return,
+            Some(job) => job,
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let mut join = Join { njobs: jobs.len(), rx: rx };
+
+        {
+            let queue = self.queue.lock().unwrap();
+
+            for mut job in jobs {
+                let done = Done(tx.clone());
+
+                let job: Job<'a> = Box::new(move || {
+                    let _done = &done;
+
+                    job()
+                });
+
+                // NB This is safe because `join` blocks until all the jobs have been executed, even
+                // if the calling thread unwinds, so the borrows captured by the jobs outlive their
+                // execution
+                unsafe {
+                    queue.send(mem::transmute::<Job<'a>, Job<'static>>(job)).unwrap();
+                }
+            }
+        }
+
+        last();
+
+        if join.wait() {
+            panic!("a job executed in the thread pool panicked")
+        }
+    }
+}
+
+/// Waits for the completion of the dispatched jobs
+struct Join {
+    njobs: usize,
+    rx: Receiver<bool>,
+}
+
+impl Join {
+    /// Blocks until all the jobs have finished, returns `true` if any of them panicked
+    fn wait(&mut self) -> bool {
+        let mut panicked = false;
+
+        while self.njobs > 0 {
+            panicked |= self.rx.recv().unwrap_or(true);
+            self.njobs -= 1;
+        }
+
+        panicked
+    }
+}
+
+impl Drop for Join {
+    fn drop(&mut self) {
+        self.wait();
+    }
+}
+
+/// Replaces a worker thread that panicked while executing a job
+struct Sentinel(Arc<Mutex<Receiver<Job<'static>>>>);
+
+impl Drop for Sentinel {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            let rx = self.0.clone();
+
+            thread::spawn(move || worker(rx));
+        }
+    }
+}
+
+fn worker(jobs: Arc<Mutex<Receiver<Job<'static>>>>) {
+    WORKER.with(|worker| worker.set(true));
+
+    let sentinel = Sentinel(jobs);
+
+    loop {
+        let job = sentinel.0.lock().unwrap().recv();
+
+        match job {
+            Ok(mut job) => job(),
+            // The pool has been dropped
+            Err(_) => break,
+        }
+    }
+}
+
+/// Returns the thread pool of this crate, spawning it if necessary
+pub fn get() -> &'static Pool {
+    static INIT: Once = ONCE_INIT;
+    static mut POOL: *const Pool = 0 as *const Pool;
+
+    unsafe {
+        INIT.call_once(|| {
+            POOL = mem::transmute(Box::new(Pool::new(num_cpus::get())));
+        });
+
+        &*POOL
+    }
+}
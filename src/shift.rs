@@ -0,0 +1,115 @@
+use std::fmt;
+use std::num::Zero;
+
+use cast::From;
+
+use convolve;
+use cost::Arith;
+use traits::{Describe, Flops, Layout, Matrix, Region, Shape, UnsafeGet, UnsafeGetLinear};
+
+impl<M> Describe for ::Shift<M> where M: Describe + UnsafeGet {
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (dr, dc) = self.1;
+
+        try!(write!(f, "Shift(({}, {}), {:?}, ", dr, dc, self.2));
+        try!(self.0.fmt_tree(f));
+        f.write_str(")")
+    }
+}
+
+impl<M> Flops for ::Shift<M> where M: Flops + UnsafeGet, M::Output: Arith {
+    #[inline(always)]
+    fn bytes() -> usize {
+        M::bytes()
+    }
+
+    // NB plus locating the neighbor, which costs as much as an addition of the element type
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        self.0.flops() + <M::Output as Arith>::add()
+    }
+}
+
+// NB Unless the shift is zero, reading any element of the destination is a hazard: the element
+// being read may have been already overwritten
+impl<M> Layout for ::Shift<M> where M: Layout + UnsafeGet {
+    #[inline(always)]
+    fn aliases(&self, dst: &Region) -> bool {
+        if self.1 == (0, 0) {
+            self.0.aliases(dst)
+        } else {
+            self.0.aliases(&Region { strides: None, ..*dst })
+        }
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        self.0.col_stride()
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        self.0.row_stride()
+    }
+}
+
+impl<M> Matrix for ::Shift<M> where M: Matrix + UnsafeGet {
+    #[inline(always)]
+    fn nrows(&self) -> u32 {
+        M::nrows(&self.0)
+    }
+
+    #[inline(always)]
+    fn ncols(&self) -> u32 {
+        M::ncols(&self.0)
+    }
+
+    #[inline(always)]
+    fn nelems(&self) -> usize {
+        M::nelems(&self.0)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> (u32, u32) {
+        M::size(&self.0)
+    }
+}
+
+impl<M> Shape for ::Shift<M> where M: Shape + UnsafeGet {
+    type Kind = M::Kind;
+}
+
+impl<M> UnsafeGet for ::Shift<M> where M: Matrix + UnsafeGet, M::Output: Zero {
+    type Output = M::Output;
+
+    #[inline(always)]
+    unsafe fn unsafe_get(&self, (i, j): (u32, u32)) -> M::Output {
+        let (nrows, ncols) = self.0.size();
+        let (dr, dc) = self.1;
+
+        let i = convolve::neighbor(self.2, i, i64::from(dr), nrows);
+        let j = convolve::neighbor(self.2, j, i64::from(dc), ncols);
+
+        match (i, j) {
+            (Some(i), Some(j)) => self.0.unsafe_get((i, j)),
+            _ => <M::Output as Zero>::zero(),
+        }
+    }
+}
+
+// NB The shifted elements may wrap around, or be clamped at the border, so this falls back to 2D
+// indexing
+impl<M> UnsafeGetLinear for ::Shift<M> where M: Matrix + UnsafeGet, M::Output: Zero {
+    #[inline(always)]
+    fn is_linear(&self, _: u32) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> M::Output {
+        let ncols = usize::from(self.ncols());
+        let (r, c) = (i / ncols, i % ncols);
+
+        self.unsafe_get((r as u32, c as u32))
+    }
+}
@@ -0,0 +1,729 @@
+//! Packet-based SIMD evaluation of `f32` and `f64` expressions
+//!
+//! `assign_simd` evaluates an expression one packet of lanes at a time: leaves load several
+//! contiguous elements at once, `Add`, `Sub`, `Mul` and `Fma` nodes operate on whole packets,
+//! comparisons produce a mask per packet that `select` uses to blend whole packets, and the
+//! remaining elements are evaluated one by one. Nodes that can't operate on packets (e.g. `map`
+//! closures) build them one lane at a time.
+//!
+//! The packet width is picked at runtime: on x86_64 processors that support AVX (checked once with
+//! `cpuid` and `xgetbv`), 256-bit packets are used; otherwise, and on other architectures, 128-bit
+//! packets are used.
+//!
+//! NB The packets are portable: they are plain arrays, and their operations are unrolled loops
+//! that LLVM turns into vector instructions of the instruction set the crate is compiled for. A
+//! binary compiled for the baseline x86_64 target processes 256-bit packets as pairs of SSE2
+//! registers, compile with `-C target-feature=+avx` to get single AVX instructions.
+
+use std::num::Zero;
+use std::ops::{Add, Mul, Sub};
+use std::sync::atomic::{ATOMIC_BOOL_INIT, AtomicBool, Ordering};
+use std::sync::{Once, ONCE_INIT};
+use std::{mem, ptr};
+
+use check;
+use cost;
+use error::{self, Result};
+use kernel::Kernel;
+use leaf::Leaf;
+use math::{Function, MulAdd};
+use policy::{self, Policy};
+use pool::{self, Job};
+use stats::{self, Recorder};
+use traits::{Expr, Matrix, Region, UnsafeGet, UnsafeGetLinear};
+
+/// A packet of lanes that are operated on simultaneously
+pub trait Simd: Add<Output=Self> + Copy + Mul<Output=Self> + Sub<Output=Self> {
+    /// The type of each lane
+    type Elem: Add<Output=Self::Elem> + Copy + Mul<Output=Self::Elem> + MulAdd + PartialOrd +
+        Sub<Output=Self::Elem>;
+
+    /// A packet of booleans, one per lane
+    type Mask: Copy;
+
+    /// Returns the number of lanes in this packet
+    fn lanes() -> usize;
+
+    /// Returns a packet where all the lanes are equal to `x`
+    fn splat(x: Self::Elem) -> Self;
+
+    /// Compares `self` with `rhs`, lane-wise
+    fn compare(self, rhs: Self, op: ::CmpOp) -> Self::Mask;
+
+    /// Computes `self * b + c`, lane-wise, rounding once
+    fn mul_add(self, b: Self, c: Self) -> Self;
+
+    /// Picks, lane-wise, the lane of `a` where `mask` is `true` and the lane of `b` elsewhere
+    fn select(mask: Self::Mask, a: Self, b: Self) -> Self;
+
+    /// Builds a packet from the elements `i..i+lanes` of `e`, one lane at a time
+    unsafe fn gather<E>(e: &E, i: usize) -> Self where E: UnsafeGetLinear<Output=Self::Elem>;
+
+    /// Builds a mask from the elements `i..i+lanes` of `e`, one lane at a time
+    unsafe fn gather_mask<E>(e: &E, i: usize) -> Self::Mask where E: UnsafeGetLinear<Output=bool>;
+
+    /// Loads a packet from the contiguous memory pointed by `src`, no alignment is required
+    unsafe fn load(src: *const Self::Elem) -> Self;
+
+    /// Loads a mask from the contiguous booleans pointed by `src`
+    unsafe fn load_mask(src: *const bool) -> Self::Mask;
+
+    /// Stores this packet in the contiguous memory pointed by `dst`, no alignment is required
+    unsafe fn store(self, dst: *mut Self::Elem);
+}
+
+macro_rules! packet {
+    ($($packet:ident: [$elem:ident; $lanes:expr]),+) => {
+        $(
+            /// A packet of SIMD lanes
+            #[allow(non_camel_case_types)]
+            #[derive(Clone, Copy)]
+            pub struct $packet([$elem; $lanes]);
+
+            impl Add for $packet {
+                type Output = $packet;
+
+                #[inline(always)]
+                fn add(self, rhs: $packet) -> $packet {
+                    let mut out = self.0;
+
+                    for (out, rhs) in out.iter_mut().zip(rhs.0.iter()) {
+                        *out = *out + *rhs
+                    }
+
+                    $packet(out)
+                }
+            }
+
+            impl Mul for $packet {
+                type Output = $packet;
+
+                #[inline(always)]
+                fn mul(self, rhs: $packet) -> $packet {
+                    let mut out = self.0;
+
+                    for (out, rhs) in out.iter_mut().zip(rhs.0.iter()) {
+                        *out = *out * *rhs
+                    }
+
+                    $packet(out)
+                }
+            }
+
+            impl Sub for $packet {
+                type Output = $packet;
+
+                #[inline(always)]
+                fn sub(self, rhs: $packet) -> $packet {
+                    let mut out = self.0;
+
+                    for (out, rhs) in out.iter_mut().zip(rhs.0.iter()) {
+                        *out = *out - *rhs
+                    }
+
+                    $packet(out)
+                }
+            }
+
+            impl Simd for $packet {
+                type Elem = $elem;
+                type Mask = [bool; $lanes];
+
+                #[inline(always)]
+                fn lanes() -> usize {
+                    $lanes
+                }
+
+                #[inline(always)]
+                fn splat(x: $elem) -> $packet {
+                    $packet([x; $lanes])
+                }
+
+                #[inline(always)]
+                fn compare(self, rhs: $packet, op: ::CmpOp) -> [bool; $lanes] {
+                    let mut out = [false; $lanes];
+
+                    for ((out, a), b) in out.iter_mut().zip(self.0.iter()).zip(rhs.0.iter()) {
+                        *out = match op {
+                            ::CmpOp::Eq => a == b,
+                            ::CmpOp::Ge => a >= b,
+                            ::CmpOp::Gt => a > b,
+                            ::CmpOp::Le => a <= b,
+                            ::CmpOp::Lt => a < b,
+                            ::CmpOp::Ne => a != b,
+                        }
+                    }
+
+                    out
+                }
+
+                #[inline(always)]
+                fn mul_add(self, b: $packet, c: $packet) -> $packet {
+                    let mut out = c.0;
+
+                    for ((out, a), b) in out.iter_mut().zip(self.0.iter()).zip(b.0.iter()) {
+                        *out = MulAdd::mul_add(*a, *b, *out)
+                    }
+
+                    $packet(out)
+                }
+
+                #[inline(always)]
+                fn select(mask: [bool; $lanes], a: $packet, b: $packet) -> $packet {
+                    let mut out = b.0;
+
+                    for ((out, mask), a) in out.iter_mut().zip(mask.iter()).zip(a.0.iter()) {
+                        if *mask {
+                            *out = *a
+                        }
+                    }
+
+                    $packet(out)
+                }
+
+                #[inline(always)]
+                unsafe fn gather<E>(e: &E, i: usize) -> $packet where
+                    E: UnsafeGetLinear<Output=$elem>,
+                {
+                    let mut out = [0 as $elem; $lanes];
+
+                    for (out, i) in out.iter_mut().zip(i..) {
+                        *out = e.unsafe_get_linear(i)
+                    }
+
+                    $packet(out)
+                }
+
+                #[inline(always)]
+                unsafe fn gather_mask<E>(e: &E, i: usize) -> [bool; $lanes] where
+                    E: UnsafeGetLinear<Output=bool>,
+                {
+                    let mut out = [false; $lanes];
+
+                    for (out, i) in out.iter_mut().zip(i..) {
+                        *out = e.unsafe_get_linear(i)
+                    }
+
+                    out
+                }
+
+                #[inline(always)]
+                unsafe fn load(src: *const $elem) -> $packet {
+                    $packet(ptr::read(src as *const [$elem; $lanes]))
+                }
+
+                #[inline(always)]
+                unsafe fn load_mask(src: *const bool) -> [bool; $lanes] {
+                    ptr::read(src as *const [bool; $lanes])
+                }
+
+                #[inline(always)]
+                unsafe fn store(self, dst: *mut $elem) {
+                    ptr::write(dst as *mut [$elem; $lanes], self.0)
+                }
+            }
+         )+
+    }
+}
+
+packet!(f32x4: [f32; 4], f32x8: [f32; 8], f64x2: [f64; 2], f64x4: [f64; 4]);
+
+/// Gets a packet of contiguous elements (in linear order), without performing bounds checks
+pub trait UnsafeGetPacket<P>: UnsafeGetLinear where P: Simd {
+    /// Returns the elements `i..i+lanes` as a packet
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P;
+}
+
+impl<'a, P, T: ?Sized> UnsafeGetPacket<P> for &'a T where P: Simd, T: UnsafeGetPacket<P> {
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        T::unsafe_get_packet(*self, i)
+    }
+}
+
+macro_rules! scalar {
+    ($($elem:ident: $($packet:ident),+);+) => {
+        $(
+            $(
+                impl UnsafeGetPacket<$packet> for $elem {
+                    #[inline(always)]
+                    unsafe fn unsafe_get_packet(&self, _: usize) -> $packet {
+                        $packet::splat(*self)
+                    }
+                }
+             )+
+         )+
+    }
+}
+
+scalar!(f32: f32x4, f32x8; f64: f64x2, f64x4);
+
+impl<A, B, P> UnsafeGetPacket<P> for ::Add<A, B> where
+    A: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    B: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        self.0.unsafe_get_packet(i) + self.1.unsafe_get_packet(i)
+    }
+}
+
+impl<F, M, P> UnsafeGetPacket<P> for ::Apply<F, M> where
+    F: Function<M::Output, Output=P::Elem>,
+    M: UnsafeGetLinear,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        P::gather(self, i)
+    }
+}
+
+impl<T, P> UnsafeGetPacket<P> for ::Col<T> where P: Simd<Elem=T>, T: Clone {
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        check::linear("Col", self.nelems(), i, P::lanes());
+
+        P::load(self.as_ref().as_ptr().offset(i as isize))
+    }
+}
+
+impl<M, K, P> UnsafeGetPacket<P> for ::Convolve<M, K> where
+    K: Kernel<Elem=P::Elem>,
+    M: Matrix + UnsafeGet<Output=P::Elem>,
+    P: Simd,
+    P::Elem: Zero,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        P::gather(self, i)
+    }
+}
+
+impl<T, R, C, P> UnsafeGetPacket<P> for ::Separable<T, R, C> where
+    C: Kernel<Elem=T>,
+    P: Simd<Elem=T>,
+    T: Clone + Zero,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        P::gather(self, i)
+    }
+}
+
+// NB Never used, user defined leaves are not linear so `assign_simd` falls back to `assign_with`
+impl<L, P> UnsafeGetPacket<P> for ::Custom<L> where L: Leaf<Elem=P::Elem>, P: Simd {
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        P::gather(self, i)
+    }
+}
+
+impl<A, B, C, P> UnsafeGetPacket<P> for ::Fma<A, B, C> where
+    A: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    B: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    C: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        let a: P = self.0.unsafe_get_packet(i);
+
+        a.mul_add(self.1.unsafe_get_packet(i), self.2.unsafe_get_packet(i))
+    }
+}
+
+impl<F, M, P> UnsafeGetPacket<P> for ::Map<F, M> where
+    F: Fn<(M::Output,), Output=P::Elem>,
+    M: UnsafeGetLinear,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        P::gather(self, i)
+    }
+}
+
+impl<T, P> UnsafeGetPacket<P> for ::Mat<T> where P: Simd<Elem=T>, T: Clone {
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        check::linear("Mat", self.nelems(), i, P::lanes());
+
+        P::load(self.as_ref().as_ptr().offset(i as isize))
+    }
+}
+
+impl<A, B, P> UnsafeGetPacket<P> for ::Mul<A, B> where
+    A: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    B: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        self.0.unsafe_get_packet(i) * self.1.unsafe_get_packet(i)
+    }
+}
+
+// NB Both branches are evaluated, and then blended using the mask
+impl<M, A, B, P> UnsafeGetPacket<P> for ::Select<M, A, B> where
+    M: UnsafeGetMask<P>,
+    A: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    B: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        let mask = self.0.unsafe_get_mask(i);
+
+        P::select(mask, self.1.unsafe_get_packet(i), self.2.unsafe_get_packet(i))
+    }
+}
+
+impl<M, P> UnsafeGetPacket<P> for ::Shift<M> where
+    M: Matrix + UnsafeGet<Output=P::Elem>,
+    P: Simd,
+    P::Elem: Zero,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        P::gather(self, i)
+    }
+}
+
+impl<A, B, P> UnsafeGetPacket<P> for ::Sub<A, B> where
+    A: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    B: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        self.0.unsafe_get_packet(i) - self.1.unsafe_get_packet(i)
+    }
+}
+
+// NB The elements of a strided vector are not contiguous, the packet has to be gathered
+impl<T, P> UnsafeGetPacket<P> for ::strided::Col<T> where P: Simd<Elem=T>, T: Clone {
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        P::gather(self, i)
+    }
+}
+
+impl<M, P> UnsafeGetPacket<P> for ::Transpose<M> where
+    M: Matrix + UnsafeGet<Output=P::Elem>,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_packet(&self, i: usize) -> P {
+        P::gather(self, i)
+    }
+}
+
+/// Gets a mask of contiguous booleans (in linear order), without performing bounds checks
+pub trait UnsafeGetMask<P>: UnsafeGetLinear<Output=bool> where P: Simd {
+    /// Returns the elements `i..i+lanes` as a mask
+    unsafe fn unsafe_get_mask(&self, i: usize) -> P::Mask;
+}
+
+impl<'a, P, T: ?Sized> UnsafeGetMask<P> for &'a T where P: Simd, T: UnsafeGetMask<P> {
+    #[inline(always)]
+    unsafe fn unsafe_get_mask(&self, i: usize) -> P::Mask {
+        T::unsafe_get_mask(*self, i)
+    }
+}
+
+impl<F, M, P> UnsafeGetMask<P> for ::Apply<F, M> where
+    F: Function<M::Output, Output=bool>,
+    M: UnsafeGetLinear,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_mask(&self, i: usize) -> P::Mask {
+        P::gather_mask(self, i)
+    }
+}
+
+impl<A, B, P> UnsafeGetMask<P> for ::Cmp<A, B> where
+    A: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    B: UnsafeGetPacket<P> + UnsafeGet<Output=P::Elem>,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_mask(&self, i: usize) -> P::Mask {
+        let a: P = self.0.unsafe_get_packet(i);
+
+        a.compare(self.1.unsafe_get_packet(i), self.2)
+    }
+}
+
+impl<P> UnsafeGetMask<P> for ::Col<bool> where P: Simd {
+    #[inline(always)]
+    unsafe fn unsafe_get_mask(&self, i: usize) -> P::Mask {
+        check::linear("Col", self.nelems(), i, P::lanes());
+
+        P::load_mask(self.as_ref().as_ptr().offset(i as isize))
+    }
+}
+
+// NB Never used, user defined leaves are not linear so `assign_simd` falls back to `assign_with`
+impl<L, P> UnsafeGetMask<P> for ::Custom<L> where L: Leaf<Elem=bool>, P: Simd {
+    #[inline(always)]
+    unsafe fn unsafe_get_mask(&self, i: usize) -> P::Mask {
+        P::gather_mask(self, i)
+    }
+}
+
+impl<F, M, P> UnsafeGetMask<P> for ::Map<F, M> where
+    F: Fn<(M::Output,), Output=bool>,
+    M: UnsafeGetLinear,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_mask(&self, i: usize) -> P::Mask {
+        P::gather_mask(self, i)
+    }
+}
+
+impl<P> UnsafeGetMask<P> for ::Mat<bool> where P: Simd {
+    #[inline(always)]
+    unsafe fn unsafe_get_mask(&self, i: usize) -> P::Mask {
+        check::linear("Mat", self.nelems(), i, P::lanes());
+
+        P::load_mask(self.as_ref().as_ptr().offset(i as isize))
+    }
+}
+
+impl<M, A, B, P> UnsafeGetMask<P> for ::Select<M, A, B> where
+    M: UnsafeGetLinear<Output=bool>,
+    A: UnsafeGetLinear<Output=bool>,
+    B: UnsafeGetLinear<Output=bool>,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_mask(&self, i: usize) -> P::Mask {
+        P::gather_mask(self, i)
+    }
+}
+
+impl<P> UnsafeGetMask<P> for ::strided::Col<bool> where P: Simd {
+    #[inline(always)]
+    unsafe fn unsafe_get_mask(&self, i: usize) -> P::Mask {
+        P::gather_mask(self, i)
+    }
+}
+
+impl<M, P> UnsafeGetMask<P> for ::Transpose<M> where
+    M: Matrix + UnsafeGet<Output=bool>,
+    P: Simd,
+{
+    #[inline(always)]
+    unsafe fn unsafe_get_mask(&self, i: usize) -> P::Mask {
+        P::gather_mask(self, i)
+    }
+}
+
+/// Returns `true` if the 256-bit packets should be used
+fn wide() -> bool {
+    static INIT: Once = ONCE_INIT;
+    static WIDE: AtomicBool = ATOMIC_BOOL_INIT;
+
+    INIT.call_once(|| {
+        let avx = has_avx();
+
+        debug!("AVX support: {}", avx);
+
+        WIDE.store(avx, Ordering::Relaxed);
+    });
+
+    WIDE.load(Ordering::Relaxed)
+}
+
+/// Checks that both the processor and the OS support AVX
+#[cfg(target_arch = "x86_64")]
+fn has_avx() -> bool {
+    unsafe {
+        let ecx: u32;
+        asm!("cpuid" : "={ecx}"(ecx) : "{eax}"(1u32) : "ebx", "edx" : "volatile");
+
+        // OSXSAVE (bit 27) and AVX (bit 28)
+        if ecx & (1 << 27) == 0 || ecx & (1 << 28) == 0 {
+            return false
+        }
+
+        // the OS saves the XMM and YMM registers on context switches
+        let eax: u32;
+        asm!("xgetbv" : "={eax}"(eax) : "{ecx}"(0u32) : "edx" : "volatile");
+
+        eax & 0b110 == 0b110
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn has_avx() -> bool {
+    false
+}
+
+macro_rules! assign {
+    ($($elem:ident: $narrow:ident, $wide:ident);+) => {
+        $(
+            impl ::Mat<$elem> {
+                /// Evaluates `rhs` and stores the result in this matrix one packet at a time (see
+                /// the `simd` module)
+                ///
+                /// Falls back to `assign_with` if some of the leaves of `rhs` are not laid out like
+                /// this matrix, or if they alias it
+                pub fn assign_simd<R>(&mut self, rhs: R) where
+                    R: Expr<Output=$elem, Kind=::kind::Mat> + Sync,
+                    R: UnsafeGetPacket<$narrow> + UnsafeGetPacket<$wide>,
+                {
+                    self.try_assign_simd(rhs).unwrap()
+                }
+
+                /// Same as `assign_simd`, but returns an error if `rhs` doesn't have the same size
+                /// as this matrix
+                pub fn try_assign_simd<R>(&mut self, rhs: R) -> Result<()> where
+                    R: Expr<Output=$elem, Kind=::kind::Mat> + Sync,
+                    R: UnsafeGetPacket<$narrow> + UnsafeGetPacket<$wide>,
+                {
+                    try!(error::check_size(self.size(), rhs.size()));
+
+                    let policy = policy::get();
+
+                    let ncols = self.ncols();
+                    if !rhs.is_linear(ncols) || rhs.aliases(&Region::of(self.as_ref(), ncols)) {
+                        return self.try_assign_with(policy, rhs)
+                    }
+
+                    unsafe {
+                        if wide() {
+                            eval::<_, $wide, _>(self.as_mut(), &rhs, policy)
+                        } else {
+                            eval::<_, $narrow, _>(self.as_mut(), &rhs, policy)
+                        }
+                    }
+
+                    Ok(())
+                }
+            }
+
+            impl ::Col<$elem> {
+                /// Evaluates `rhs` and stores the result in this vector one packet at a time (see
+                /// the `simd` module)
+                ///
+                /// Falls back to `assign_with` if some of the leaves of `rhs` are not laid out like
+                /// this vector, or if they alias it
+                pub fn assign_simd<R>(&mut self, rhs: R) where
+                    R: Expr<Output=$elem, Kind=::kind::Col> + Sync,
+                    R: UnsafeGetPacket<$narrow> + UnsafeGetPacket<$wide>,
+                {
+                    self.try_assign_simd(rhs).unwrap()
+                }
+
+                /// Same as `assign_simd`, but returns an error if `rhs` doesn't have the same size
+                /// as this vector
+                pub fn try_assign_simd<R>(&mut self, rhs: R) -> Result<()> where
+                    R: Expr<Output=$elem, Kind=::kind::Col> + Sync,
+                    R: UnsafeGetPacket<$narrow> + UnsafeGetPacket<$wide>,
+                {
+                    try!(error::check_size(self.size(), rhs.size()));
+
+                    let policy = policy::get();
+
+                    if !rhs.is_linear(1) || rhs.aliases(&Region::of(self.as_ref(), 1)) {
+                        return self.try_assign_with(policy, rhs)
+                    }
+
+                    unsafe {
+                        if wide() {
+                            eval::<_, $wide, _>(self.as_mut(), &rhs, policy)
+                        } else {
+                            eval::<_, $narrow, _>(self.as_mut(), &rhs, policy)
+                        }
+                    }
+
+                    Ok(())
+                }
+            }
+         )+
+    }
+}
+
+assign!(f32: f32x4, f32x8; f64: f64x2, f64x4);
+
+/// Evaluates `rhs` into `dst` one packet at a time
+///
+/// Unsafe because `rhs` must have the same size as `dst`, and be laid out like it
+unsafe fn eval<T, P, R>(dst: &mut [T], rhs: &R, policy: Policy) where
+    P: Simd<Elem=T>,
+    R: Expr<Output=T> + Sync + UnsafeGetPacket<P>,
+    T: Send,
+{
+    let nelems = dst.len();
+    let cost = cost::of::<T, R>(rhs, nelems);
+
+    debug!("Estimated cost: {}", cost);
+
+    if nelems == 0 {
+        return
+    }
+
+    let bytes = (R::bytes() + mem::size_of::<T>()).saturating_mul(nelems);
+    let recorder = Recorder::start();
+    let nthreads = policy.nthreads(cost);
+    let expr = check::describe(rhs);
+
+    {
+        let recorder = recorder.as_ref();
+        let expr = &expr;
+
+        // Fills `stripe` with the elements of `rhs` that start at the linear index `offset`
+        let run = |stripe: &mut [T], offset: usize| {
+            stats::chunk(recorder, offset, stripe.len(), || {
+                check::within(expr, || packets::<T, P, R>(stripe, offset, rhs))
+            })
+        };
+        let run = &run;
+
+        if nthreads == 1 {
+            run(dst, 0)
+        } else {
+            debug!("Spinning up {} threads", nthreads);
+
+            // elements per stripe, a multiple of the number of lanes
+            let lanes = P::lanes();
+            let sz = ((nelems - 1) / nthreads / lanes + 1) * lanes;
+
+            pool::get().execute(dst.chunks_mut(sz).zip(0..).map(move |(stripe, i)| {
+                Box::new(move || {
+                    run(stripe, i * sz)
+                }) as Job
+            }).collect());
+        }
+    }
+
+    stats::finish(recorder, nelems, bytes, cost, nthreads)
+}
+
+/// Evaluates the elements of `rhs` that start at the linear index `offset` into `stripe`
+unsafe fn packets<T, P, R>(stripe: &mut [T], offset: usize, rhs: &R) where
+    P: Simd<Elem=T>,
+    R: UnsafeGet<Output=T> + UnsafeGetPacket<P>,
+{
+    let lanes = P::lanes();
+    let n = stripe.len() / lanes * lanes;
+    let ptr = stripe.as_mut_ptr();
+
+    let mut k = 0;
+    while k < n {
+        let packet: P = rhs.unsafe_get_packet(offset + k);
+        packet.store(ptr.offset(k as isize));
+
+        k += lanes;
+    }
+
+    // scalar tail
+    for (dst, k) in stripe[n..].iter_mut().zip(offset + n..) {
+        *dst = rhs.unsafe_get_linear(k)
+    }
+}
@@ -0,0 +1,143 @@
+//! Evaluation statistics
+//!
+//! A hook installed with `set_hook` is called after each assignment (including `assign_simd` and
+//! fused assignments) with the `Stats` of that assignment. The evaluators only read the clock while
+//! a hook is installed.
+//!
+//! ``` ignore
+//! fn report(stats: &et::stats::Stats) {
+//!     println!("{} elements, {} threads, {} ns, {:.2} GB/s",
+//!              stats.nelems, stats.nthreads, stats.elapsed_ns, stats.gb_per_s());
+//! }
+//!
+//! et::stats::set_hook(report);
+//! ```
+
+use std::mem;
+use std::sync::Mutex;
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+
+use time;
+
+static HOOK: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// The evaluation of a chunk of consecutive elements of the destination, by one thread
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Chunk {
+    /// The linear (row major) index of the first element of the chunk
+    pub start: usize,
+    /// The number of elements of the chunk
+    pub nelems: usize,
+    /// Time spent evaluating the chunk, in nanoseconds
+    pub elapsed_ns: u64,
+}
+
+/// Statistics of an assignment
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stats {
+    /// Estimated number of bytes read from the operands and written to the destination
+    pub bytes: usize,
+    /// The chunks the destination was split into, in completion order
+    pub chunks: Vec<Chunk>,
+    /// Estimated cost of the assignment (see the `cost` module)
+    pub cost: usize,
+    /// Time spent in the assignment, in nanoseconds
+    pub elapsed_ns: u64,
+    /// Number of elements written
+    pub nelems: usize,
+    /// Number of threads used
+    pub nthreads: usize,
+}
+
+impl Stats {
+    /// Returns the achieved memory throughput, in GB/s
+    pub fn gb_per_s(&self) -> f64 {
+        if self.elapsed_ns == 0 {
+            0.
+        } else {
+            self.bytes as f64 / self.elapsed_ns as f64
+        }
+    }
+}
+
+/// Calls `hook` after each assignment, replacing the previous hook
+pub fn set_hook(hook: fn(&Stats)) {
+    HOOK.store(hook as usize, Ordering::SeqCst)
+}
+
+/// Removes the hook, if any
+pub fn clear_hook() {
+    HOOK.store(0, Ordering::SeqCst)
+}
+
+/// Records the statistics of an assignment, used by the evaluators
+pub struct Recorder {
+    chunks: Mutex<Vec<Chunk>>,
+    hook: fn(&Stats),
+    start: u64,
+}
+
+impl Recorder {
+    /// Starts recording an assignment, returns `None` if no hook is installed
+    pub fn start() -> Option<Recorder> {
+        match HOOK.load(Ordering::SeqCst) {
+            0 => None,
+            hook => Some(Recorder {
+                chunks: Mutex::new(vec![]),
+                hook: unsafe { mem::transmute(hook) },
+                start: time::precise_time_ns(),
+            }),
+        }
+    }
+
+    /// Evaluates `f`, which fills `nelems` elements of the destination starting at the linear index
+    /// `start`, and records the time it took
+    pub fn chunk<F>(&self, start: usize, nelems: usize, f: F) where F: FnOnce() {
+        let t0 = time::precise_time_ns();
+        f();
+        let elapsed_ns = time::precise_time_ns() - t0;
+
+        self.chunks.lock().unwrap().push(Chunk {
+            start: start,
+            nelems: nelems,
+            elapsed_ns: elapsed_ns,
+        })
+    }
+
+    /// Finishes recording the assignment and reports its statistics to the hook
+    pub fn finish(self, nelems: usize, bytes: usize, cost: usize, nthreads: usize) {
+        let elapsed_ns = time::precise_time_ns() - self.start;
+
+        (self.hook)(&Stats {
+            bytes: bytes,
+            chunks: self.chunks.into_inner().unwrap(),
+            cost: cost,
+            elapsed_ns: elapsed_ns,
+            nelems: nelems,
+            nthreads: nthreads,
+        })
+    }
+}
+
+/// Finishes recording the assignment (see `Recorder::finish`), if `recorder` is not `None`
+#[inline(always)]
+pub fn finish(recorder: Option<Recorder>,
+              nelems: usize,
+              bytes: usize,
+              cost: usize,
+              nthreads: usize) {
+    if let Some(recorder) = recorder {
+        recorder.finish(nelems, bytes, cost, nthreads)
+    }
+}
+
+/// Evaluates `f` (see `Recorder::chunk`), timing it if `recorder` is not `None`
+#[inline(always)]
+pub fn chunk<F>(recorder: Option<&Recorder>, start: usize, nelems: usize, f: F) where
+    F: FnOnce(),
+{
+    match recorder {
+        None => f(),
+        Some(recorder) => recorder.chunk(start, nelems, f),
+    }
+}
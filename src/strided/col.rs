@@ -1,6 +1,9 @@
-use std::mem;
+use std::{fmt, mem};
 
-use traits::{Flops, Matrix, UnsafeGet};
+use cast::From;
+
+use check;
+use traits::{Describe, Flops, Layout, Matrix, Region, Shape, UnsafeGet, UnsafeGetLinear};
 
 impl<T> ::strided::Col<T> {
     pub fn map<F>(&self, f: F) -> ::Map<F, &Self> where F: Fn<(T,)>, T: Clone {
@@ -14,12 +17,50 @@ impl<T> ::strided::Col<T> {
     }
 }
 
+impl<T> Describe for ::strided::Col<T> {
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ::strided::raw::Slice { len, stride, .. } = self.repr();
+
+        write!(f, "strided::Col[{}; stride {}]", len, stride)
+    }
+}
+
 impl<T> Flops for ::strided::Col<T> {
-    fn flops() -> usize {
+    fn bytes() -> usize {
+        mem::size_of::<T>()
+    }
+
+    fn flops(&self) -> usize {
         0
     }
 }
 
+// NB A column vector has a single column, so there's no `(i, j + 1)` element
+impl<T> Layout for ::strided::Col<T> {
+    fn aliases(&self, dst: &Region) -> bool {
+        let ::strided::raw::Slice { data, len, stride, .. } = self.repr();
+
+        if len == 0 {
+            return false
+        }
+
+        let start = data as usize;
+        let end = start + check::span(len, stride) * mem::size_of::<T>();
+
+        dst.is_aliased_by(start, end, (self.row_stride(), self.col_stride()))
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        mem::size_of::<T>()
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        self.repr().stride as usize * mem::size_of::<T>()
+    }
+}
+
 impl<T> Matrix for ::strided::Col<T> {
     #[inline(always)]
     fn nrows(&self) -> u32 {
@@ -34,15 +75,40 @@ impl<T> Matrix for ::strided::Col<T> {
 
 unsafe impl<T> Send for ::strided::Col<T> where T: Send {}
 
+impl<T> Shape for ::strided::Col<T> {
+    type Kind = ::kind::Col;
+}
+
 unsafe impl<T> Sync for ::strided::Col<T> where T: Sync {}
 
 impl<T> UnsafeGet for ::strided::Col<T> where T: Clone {
     type Output = T;
 
     #[inline(always)]
-    unsafe fn unsafe_get(&self, (i, _): (u32, u32)) -> T {
-        let ::strided::raw::Slice { data, stride, .. } = self.repr();
+    unsafe fn unsafe_get(&self, (i, j): (u32, u32)) -> T {
+        let ::strided::raw::Slice { data, len, stride, alloc } = self.repr();
+
+        check::index("strided::Col", (len, 1), (i, j));
+        let k = usize::from(i) * usize::from(stride);
+
+        (*data.offset(check::offset("strided::Col", (i, j), k, alloc))).clone()
+    }
+}
+
+// NB The linear index of the element `(i, 0)` of a column vector is `i`, regardless of its stride
+impl<T> UnsafeGetLinear for ::strided::Col<T> where T: Clone {
+    #[inline(always)]
+    fn is_linear(&self, ncols: u32) -> bool {
+        ncols == 1
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> T {
+        let ::strided::raw::Slice { data, len, stride, alloc } = self.repr();
+
+        check::linear("strided::Col", usize::from(len), i, 1);
+        let k = i * usize::from(stride);
 
-        (*data.offset(i as isize * stride as isize)).clone()
+        (*data.offset(check::offset("strided::Col", (i as u32, 0), k, alloc))).clone()
     }
 }
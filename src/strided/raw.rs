@@ -2,4 +2,6 @@ pub struct Slice<T> {
     pub data: *mut T,
     pub len: u32,
     pub stride: u32,
+    /// Number of elements of the underlying allocation, starting at `data`
+    pub alloc: usize,
 }
@@ -0,0 +1,137 @@
+use std::{cmp, fmt};
+use std::ops::Sub;
+
+use cost::Arith;
+use kind::Broadcast;
+use traits::{Describe, Flops, Layout, Matrix, Region, Shape, UnsafeGet, UnsafeGetLinear};
+
+impl<A, B, C> ::Sub<A, B> where
+    A: UnsafeGet,
+    B: UnsafeGet,
+    A::Output: Sub<B::Output, Output=C>,
+{
+    /// Lazily maps this subtraction
+    pub fn map<F>(self, f: F) -> ::Map<F, ::Sub<A, B>> where
+        F: Fn<(C,)>,
+    {
+        ::Map(f, self)
+    }
+}
+
+impl<A, B> Describe for ::Sub<A, B> where
+    A: Describe + UnsafeGet,
+    B: Describe + UnsafeGet,
+    A::Output: Sub<B::Output>,
+{
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(f.write_str("Sub("));
+        try!(self.0.fmt_tree(f));
+        try!(f.write_str(", "));
+        try!(self.1.fmt_tree(f));
+        f.write_str(")")
+    }
+}
+
+impl<A, B> Flops for ::Sub<A, B> where
+    A: Flops + UnsafeGet,
+    B: Flops + UnsafeGet,
+    A::Output: Sub<B::Output> + Arith,
+{
+    #[inline(always)]
+    fn bytes() -> usize {
+        A::bytes() + B::bytes()
+    }
+
+    // NB A subtraction costs as much as an addition
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        self.0.flops() + self.1.flops() + <A::Output as Arith>::add()
+    }
+}
+
+impl<A, B> Layout for ::Sub<A, B> where
+    A: Layout + UnsafeGet,
+    B: Layout + UnsafeGet,
+    A::Output: Sub<B::Output>,
+{
+    #[inline(always)]
+    fn aliases(&self, dst: &Region) -> bool {
+        self.0.aliases(dst) || self.1.aliases(dst)
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        cmp::max(self.0.col_stride(), self.1.col_stride())
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        cmp::max(self.0.row_stride(), self.1.row_stride())
+    }
+}
+
+// NB Unlike in a sum, the scalar (if any) is placed on the right side of a subtraction (`x - 1.`),
+// so the size is taken from the left operand
+impl<A, B, C> Matrix for ::Sub<A, B> where
+    A: Matrix + UnsafeGet,
+    B: UnsafeGet,
+    A::Output: Sub<B::Output, Output=C>,
+{
+    #[inline(always)]
+    fn nrows(&self) -> u32 {
+        A::nrows(&self.0)
+    }
+
+    #[inline(always)]
+    fn ncols(&self) -> u32 {
+        A::ncols(&self.0)
+    }
+
+    #[inline(always)]
+    fn nelems(&self) -> usize {
+        A::nelems(&self.0)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> (u32, u32) {
+        A::size(&self.0)
+    }
+}
+
+impl<A, B> Shape for ::Sub<A, B> where
+    A: Shape + UnsafeGet,
+    B: Shape + UnsafeGet,
+    A::Output: Sub<B::Output>,
+    A::Kind: Broadcast<B::Kind>,
+{
+    type Kind = <A::Kind as Broadcast<B::Kind>>::Output;
+}
+
+impl<A, B, C> UnsafeGet for ::Sub<A, B> where
+    A: UnsafeGet,
+    B: UnsafeGet,
+    A::Output: Sub<B::Output, Output=C>,
+{
+    type Output = C;
+
+    #[inline(always)]
+    unsafe fn unsafe_get(&self, i: (u32, u32)) -> C {
+        self.0.unsafe_get(i) - self.1.unsafe_get(i)
+    }
+}
+
+impl<A, B, C> UnsafeGetLinear for ::Sub<A, B> where
+    A: UnsafeGetLinear,
+    B: UnsafeGetLinear,
+    A::Output: Sub<B::Output, Output=C>,
+{
+    #[inline(always)]
+    fn is_linear(&self, ncols: u32) -> bool {
+        self.0.is_linear(ncols) && self.1.is_linear(ncols)
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> C {
+        self.0.unsafe_get_linear(i) - self.1.unsafe_get_linear(i)
+    }
+}
@@ -1,20 +1,214 @@
+use std::{fmt, intrinsics, mem};
+
 use cast::From;
 
-/// A rough measurement of the (floating point) operations per element that will be required to
-/// evaluate this lazy expression
+/// A rough measurement of the work per element that will be required to evaluate this lazy
+/// expression (see the `cost` module)
 pub trait Flops {
-    /// Returns the number of (floating point) operations per element required to evaluate this
-    /// expression
-    fn flops() -> usize;
+    /// Returns the number of bytes read from memory per element
+    fn bytes() -> usize;
+
+    /// Returns the cost of the operations per element required to evaluate this expression, in
+    /// units of an `f32` addition
+    fn flops(&self) -> usize;
 }
 
 impl<'a, T: ?Sized> Flops for &'a T where T: Flops {
     #[inline(always)]
-    fn flops() -> usize {
-        T::flops()
+    fn bytes() -> usize {
+        T::bytes()
+    }
+
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        T::flops(*self)
+    }
+}
+
+/// The memory written to by an assignment
+#[derive(Clone, Copy, Debug)]
+pub struct Region {
+    /// Address of the first byte of the region
+    pub start: usize,
+    /// Address of the byte past the end of the region
+    pub end: usize,
+    /// The row and column strides, in bytes, of the destination. `None` if reading any element of
+    /// the region is a hazard
+    pub strides: Option<(usize, usize)>,
+}
+
+impl Region {
+    /// Returns the region of the buffer `dst`, a row major matrix with `ncols` columns
+    pub fn of<T>(dst: &[T], ncols: u32) -> Region {
+        let size = mem::size_of::<T>();
+        let start = dst.as_ptr() as usize;
+
+        Region {
+            start: start,
+            end: start + dst.len() * size,
+            strides: Some((usize::from(ncols) * size, size)),
+        }
+    }
+
+    /// Checks if a leaf that spans the memory `start..end`, and has the given row and column
+    /// `strides`, reads elements of this region that are not the element being written
+    ///
+    /// NB A leaf that is laid out exactly like the destination (e.g. `y[..] = &y * 2.`) is not
+    /// considered an alias, because each element is read right before it gets overwritten
+    pub fn is_aliased_by(&self, start: usize, end: usize, strides: (usize, usize)) -> bool {
+        if start >= self.end || end <= self.start {
+            false
+        } else {
+            match self.strides {
+                None => true,
+                Some(s) => start != self.start || strides != s,
+            }
+        }
+    }
+
+    /// Returns the region as seen from a transposed leaf
+    pub fn transpose(&self) -> Region {
+        Region {
+            strides: self.strides.map(|(r, c)| (c, r)),
+            ..*self
+        }
     }
 }
 
+/// Memory layout of the leaves of an expression, used by the evaluator to pick a traversal order
+/// and to detect aliasing
+pub trait Layout {
+    /// Checks if any of the leaves of this expression reads an element of `dst` that is not the
+    /// element being written
+    fn aliases(&self, dst: &Region) -> bool;
+
+    /// Returns the largest distance, in bytes, between the elements `(i, j)` and `(i, j + 1)` of
+    /// any of the leaves of this expression
+    fn col_stride(&self) -> usize;
+
+    /// Returns the largest distance, in bytes, between the elements `(i, j)` and `(i + 1, j)` of
+    /// any of the leaves of this expression
+    fn row_stride(&self) -> usize;
+}
+
+impl<'a, T: ?Sized> Layout for &'a T where T: Layout {
+    #[inline(always)]
+    fn aliases(&self, dst: &Region) -> bool {
+        T::aliases(*self, dst)
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        T::col_stride(*self)
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        T::row_stride(*self)
+    }
+}
+
+/// Renders the tree of proxies of an expression, see `Expr::describe`
+pub trait Describe {
+    /// Writes the tree of proxies of this expression, e.g. `Add(Col[1080], 2)`, into `f`
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result;
+}
+
+impl<'a, T: ?Sized> Describe for &'a T where T: Describe {
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        T::fmt_tree(*self, f)
+    }
+}
+
+/// A printable rendering of an expression, returned by `Expr::describe`
+pub struct Description<'a, E: ?Sized + 'a>(&'a E);
+
+impl<'a, E: ?Sized> fmt::Debug for Description<'a, E> where E: Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<'a, E: ?Sized> fmt::Display for Description<'a, E> where E: Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (nrows, ncols) = self.0.size();
+
+        try!(self.0.fmt_tree(f));
+        write!(f, " [{}x{}, {} flops and {} bytes read per element]",
+               nrows, ncols, self.0.flops(), E::bytes())
+    }
+}
+
+/// Returns the name of the type `T`, including its generic arguments, without module paths, e.g.
+/// `Rounding<u8>`
+pub fn type_name<T: ?Sized>() -> String {
+    let name = unsafe { intrinsics::type_name::<T>() };
+    let mut out = String::with_capacity(name.len());
+    // where the current path segment starts in `out`
+    let mut start = 0;
+
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            // drop the module that precedes `::`
+            chars.next();
+            out.truncate(start);
+        } else {
+            out.push(c);
+
+            if !(c.is_alphanumeric() || c == '_') {
+                start = out.len();
+            }
+        }
+    }
+
+    out
+}
+
+/// A lazy expression, or one of its leaves, that can be evaluated by the indexed assignment
+/// operator
+///
+/// This is the bound to use when writing functions that are generic over expressions, e.g.:
+///
+/// ``` ignore
+/// fn scale<E>(y: &mut Col<f32>, x: E) where E: Expr<Output=f32, Kind=kind::Col> + Sync {
+///     y[..] = x.sqrt();
+/// }
+/// ```
+///
+/// This trait is implemented for all the expressions built by this crate, and it can't be
+/// implemented outside of it because the unchecked accessors that the evaluator uses are sealed.
+/// To add a new kind of leaf, implement `Leaf` and wrap the value with `et::leaf`.
+pub trait Expr: Describe + Flops + Layout + Matrix + Shape + UnsafeGet + UnsafeGetLinear {
+    /// Returns the estimated number of (floating point) operations required to evaluate the whole
+    /// expression
+    fn cost(&self) -> usize {
+        self.flops() * self.nelems()
+    }
+
+    /// Returns a printable rendering of the tree of proxies of this expression, along with its
+    /// size and its estimated cost, e.g.
+    /// `Add(Mul(2, Col[1080]), Col[1080]) [1080x1, 2 flops and 8 bytes read per element]`
+    fn describe(&self) -> Description<Self> {
+        Description(self)
+    }
+
+    /// Returns the element at the coordinates `(i, j)`, or `None` if they are out of range
+    fn get(&self, (i, j): (u32, u32)) -> Option<Self::Output> {
+        let (nrows, ncols) = self.size();
+
+        if i < nrows && j < ncols {
+            Some(unsafe { self.unsafe_get((i, j)) })
+        } else {
+            None
+        }
+    }
+}
+
+impl<E> Expr for E where
+    E: Describe + Flops + Layout + Matrix + Shape + UnsafeGet + UnsafeGetLinear,
+{}
+
 /// A matrix, a rectangular array arranged in rows and columns
 pub trait Matrix {
     /// Returns the number of rows of this matrix
@@ -65,8 +259,24 @@ impl<'a, M: ?Sized> Matrix for &'a M where M: Matrix {
     }
 }
 
-// FIXME this should be a sealed trait
+/// An operand of an element-wise operation: a matrix, or a scalar that's broadcasted to the size of
+/// the other operands
+pub trait Operand {
+    /// Returns the size of this operand, or `None` if it's a scalar
+    fn operand_size(&self) -> Option<(u32, u32)>;
+}
+
+impl<M: ?Sized> Operand for M where M: Matrix {
+    #[inline(always)]
+    fn operand_size(&self) -> Option<(u32, u32)> {
+        Some(self.size())
+    }
+}
+
 /// Gets an element of the collection, without performing bounds checks
+///
+/// NB This trait is sealed: this module is private, so it can't be implemented outside this crate.
+/// User defined leaves implement the safe `Leaf` trait instead
 pub trait UnsafeGet {
     /// The element
     type Output;
@@ -84,16 +294,93 @@ impl<'a, T: ?Sized> UnsafeGet for &'a T where T: UnsafeGet {
     }
 }
 
+/// The shape kind of an expression, checked at compile time (see the `kind` module)
+pub trait Shape {
+    /// `kind::Col`, `kind::Mat` or `kind::Scalar`
+    type Kind;
+}
+
+impl<'a, T: ?Sized> Shape for &'a T where T: Shape {
+    type Kind = T::Kind;
+}
+
+/// Gets an element of the collection using its linear (row major) index, without performing
+/// bounds checks
+///
+/// When all the leaves of an expression are contiguous and have the same number of columns as the
+/// destination, the evaluator uses this accessor instead of `UnsafeGet` and runs a flat loop that
+/// LLVM can vectorize.
+pub trait UnsafeGetLinear: UnsafeGet {
+    /// Returns `true` if the element `(i, j)` of this expression is the element `i * ncols + j` in
+    /// linear order
+    fn is_linear(&self, ncols: u32) -> bool;
+
+    /// Returns the element at the linear index `i`
+    unsafe fn unsafe_get_linear(&self, i: usize) -> Self::Output;
+}
+
+impl<'a, T: ?Sized> UnsafeGetLinear for &'a T where T: UnsafeGetLinear {
+    #[inline(always)]
+    fn is_linear(&self, ncols: u32) -> bool {
+        T::is_linear(*self, ncols)
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> T::Output {
+        T::unsafe_get_linear(*self, i)
+    }
+}
+
 macro_rules! scalar {
     ($($ty:ty),+) => {
         $(
+            impl Describe for $ty {
+                fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "{:?}", self)
+                }
+            }
+
             impl Flops for $ty {
                 #[inline(always)]
-                fn flops() -> usize {
+                fn bytes() -> usize {
+                    0
+                }
+
+                #[inline(always)]
+                fn flops(&self) -> usize {
                     0
                 }
             }
 
+            // NB Scalars are broadcasted, they are never read from memory during the evaluation
+            impl Layout for $ty {
+                #[inline(always)]
+                fn aliases(&self, _: &Region) -> bool {
+                    false
+                }
+
+                #[inline(always)]
+                fn col_stride(&self) -> usize {
+                    0
+                }
+
+                #[inline(always)]
+                fn row_stride(&self) -> usize {
+                    0
+                }
+            }
+
+            impl Operand for $ty {
+                #[inline(always)]
+                fn operand_size(&self) -> Option<(u32, u32)> {
+                    None
+                }
+            }
+
+            impl Shape for $ty {
+                type Kind = ::kind::Scalar;
+            }
+
             impl UnsafeGet for $ty {
                 type Output = $ty;
 
@@ -102,8 +389,88 @@ macro_rules! scalar {
                     *self
                 }
             }
+
+            impl UnsafeGetLinear for $ty {
+                #[inline(always)]
+                fn is_linear(&self, _: u32) -> bool {
+                    true
+                }
+
+                #[inline(always)]
+                unsafe fn unsafe_get_linear(&self, _: usize) -> $ty {
+                    *self
+                }
+            }
          )+
     }
 }
 
 scalar!(f32, f64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use super::Region;
+
+    /// Size of an `f32`
+    const SZ: usize = 4;
+
+    /// Returns the region of a 2x4 destination that occupies the first half of `buf`, along with
+    /// the address of `buf`
+    fn dst(buf: &[f32; 16]) -> (Region, usize) {
+        assert_eq!(mem::size_of::<f32>(), SZ);
+
+        (Region::of(&buf[..8], 4), buf.as_ptr() as usize)
+    }
+
+    #[test]
+    fn same_layout() {
+        let buf = [0.; 16];
+        let (dst, start) = dst(&buf);
+
+        assert!(!dst.is_aliased_by(start, start + 8 * SZ, (4 * SZ, SZ)));
+    }
+
+    #[test]
+    fn disjoint() {
+        let buf = [0.; 16];
+        let (dst, start) = dst(&buf);
+
+        assert!(!dst.is_aliased_by(start + 8 * SZ, start + 16 * SZ, (4 * SZ, SZ)));
+        assert!(!dst.is_aliased_by(start + 8 * SZ, start + 16 * SZ, (SZ, 4 * SZ)));
+    }
+
+    #[test]
+    fn overlapping() {
+        let buf = [0.; 16];
+        let (dst, start) = dst(&buf);
+
+        // shifted by one element
+        assert!(dst.is_aliased_by(start + SZ, start + 9 * SZ, (4 * SZ, SZ)));
+        // transposed
+        assert!(dst.is_aliased_by(start, start + 8 * SZ, (SZ, 4 * SZ)));
+        // the first column, viewed as a vector
+        assert!(dst.is_aliased_by(start, start + 5 * SZ, (4 * SZ, 4 * SZ)));
+    }
+
+    #[test]
+    fn transposed() {
+        let buf = [0.; 16];
+        let (dst, start) = dst(&buf);
+
+        assert!(!dst.transpose().is_aliased_by(start, start + 8 * SZ, (SZ, 4 * SZ)));
+        assert!(dst.transpose().is_aliased_by(start, start + 8 * SZ, (4 * SZ, SZ)));
+    }
+
+    #[test]
+    fn no_strides() {
+        let buf = [0.; 16];
+        let (dst, start) = dst(&buf);
+        let dst = Region { strides: None, ..dst };
+
+        assert!(dst.is_aliased_by(start, start + 8 * SZ, (4 * SZ, SZ)));
+        assert!(dst.is_aliased_by(start + 7 * SZ, start + 8 * SZ, (4 * SZ, SZ)));
+        assert!(!dst.is_aliased_by(start + 8 * SZ, start + 16 * SZ, (4 * SZ, SZ)));
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+
+use cast::From;
+
+use kind::Transposed;
+use traits::{Describe, Flops, Layout, Matrix, Region, Shape, UnsafeGet, UnsafeGetLinear};
+
+impl<M> Describe for ::Transpose<M> where M: Describe + UnsafeGet {
+    fn fmt_tree(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(f.write_str("Transpose("));
+        try!(self.0.fmt_tree(f));
+        f.write_str(")")
+    }
+}
+
+impl<M> Flops for ::Transpose<M> where M: Flops + UnsafeGet {
+    #[inline(always)]
+    fn bytes() -> usize {
+        M::bytes()
+    }
+
+    #[inline(always)]
+    fn flops(&self) -> usize {
+        self.0.flops()
+    }
+}
+
+impl<M> Layout for ::Transpose<M> where M: Layout + UnsafeGet {
+    #[inline(always)]
+    fn aliases(&self, dst: &Region) -> bool {
+        self.0.aliases(&dst.transpose())
+    }
+
+    #[inline(always)]
+    fn col_stride(&self) -> usize {
+        self.0.row_stride()
+    }
+
+    #[inline(always)]
+    fn row_stride(&self) -> usize {
+        self.0.col_stride()
+    }
+}
+
+impl<M> Matrix for ::Transpose<M> where M: Matrix + UnsafeGet {
+    #[inline(always)]
+    fn nrows(&self) -> u32 {
+        M::ncols(&self.0)
+    }
+
+    #[inline(always)]
+    fn ncols(&self) -> u32 {
+        M::nrows(&self.0)
+    }
+
+    #[inline(always)]
+    fn nelems(&self) -> usize {
+        M::nelems(&self.0)
+    }
+
+    #[inline(always)]
+    fn size(&self) -> (u32, u32) {
+        let (nrows, ncols) = M::size(&self.0);
+        (ncols, nrows)
+    }
+}
+
+impl<M> Shape for ::Transpose<M> where M: Shape + UnsafeGet, M::Kind: Transposed {
+    type Kind = <M::Kind as Transposed>::Output;
+}
+
+impl<M> UnsafeGet for ::Transpose<M> where M: UnsafeGet {
+    type Output = M::Output;
+
+    #[inline(always)]
+    unsafe fn unsafe_get(&self, (i, j): (u32, u32)) -> M::Output {
+        self.0.unsafe_get((j, i))
+    }
+}
+
+// NB The elements of a transposed matrix are never in linear order (unless it's a vector), so this
+// falls back to 2D indexing
+impl<M> UnsafeGetLinear for ::Transpose<M> where M: Matrix + UnsafeGet {
+    #[inline(always)]
+    fn is_linear(&self, _: u32) -> bool {
+        false
+    }
+
+    #[inline(always)]
+    unsafe fn unsafe_get_linear(&self, i: usize) -> M::Output {
+        let ncols = usize::from(self.ncols());
+        let (r, c) = (i / ncols, i % ncols);
+
+        self.0.unsafe_get((c as u32, r as u32))
+    }
+}